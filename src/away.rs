@@ -0,0 +1,75 @@
+// What happened between the player's last check-in and this one.
+
+use serde::{Deserialize, Serialize};
+
+use crate::log::LogEvent;
+use crate::outcome::AwaySummary;
+use crate::state::State;
+
+// The state as the player last saw it.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct Snapshot {
+    ts: i64,
+    // Start time and bot mass of the trial running at the time
+    trial: Option<(i64, f64)>,
+    unease: f64,
+    inspiration: f64
+}
+
+impl State {
+    // Remembers the game as the player sees it now, to compare against at
+    // their next check-in. Loading the save for anything else doesn't count.
+    pub fn mark_seen(&mut self) {
+        self.last_seen = Some(Snapshot {
+            ts: self.clock.now(),
+            trial: self.trial_in_progress.as_ref().map(|t| (t.start_ts, t.bot_mass)),
+            unease: self.population_unease,
+            inspiration: self.scientific_inspiration
+        });
+    }
+
+    // None if the player has never checked in.
+    pub fn away_summary(&self) -> Option<AwaySummary> {
+        let before = self.last_seen.as_ref()?;
+
+        let mut research_completed: Vec<String> = Vec::new();
+        let mut events: Vec<(String, String)> = Vec::new();
+        for entry in self.log.iter().filter(|e| e.ts > before.ts) {
+            match &entry.event {
+                LogEvent::ResearchCompleted { name } => research_completed.push(name.clone()),
+                LogEvent::TrialEvent { name, description }
+                | LogEvent::UneaseConsequence { name, description } => events.push((name.clone(), description.clone())),
+                _ => ()
+            }
+        }
+
+        // Only comparable if it is still the same trial
+        let (mass_growth, milestones) = match (before.trial, &self.trial_in_progress) {
+            (Some((start_ts, mass_before)), Some(trial)) if trial.start_ts == start_ts && mass_before > 0f64 => {
+                (Some(trial.bot_mass / mass_before), mass_milestones(mass_before, trial.bot_mass))
+            },
+            _ => (None, Vec::new())
+        };
+
+        Some(AwaySummary {
+            away: self.clock.now() - before.ts,
+            research_completed,
+            events,
+            mass_growth,
+            unease_change: self.population_unease - before.unease,
+            inspiration_change: self.scientific_inspiration - before.inspiration,
+            milestones
+        })
+    }
+}
+
+// Powers of a thousand grams (1kg, 1t, 1kt, ...) passed on the way from one
+// mass to another.
+fn mass_milestones(from: f64, to: f64) -> Vec<f64> {
+    if to <= from {
+        return Vec::new();
+    }
+    let first = (from.log10() / 3f64).floor() as i32 + 1;
+    let last = (to.log10() / 3f64).floor() as i32;
+    (first.max(0)..=last).map(|p| 1000f64.powi(p)).collect()
+}
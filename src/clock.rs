@@ -0,0 +1,46 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use chrono::Utc;
+
+// Source of the current time, as a unix timestamp in seconds.
+pub trait Clock {
+    fn now(&self) -> i64;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> i64 {
+        Utc::now().timestamp()
+    }
+}
+
+// A clock that only moves when told to, for reproducible simulations.
+pub struct ManualClock {
+    now: Cell<i64>
+}
+
+impl ManualClock {
+    pub fn new(now: i64) -> ManualClock {
+        ManualClock { now: Cell::new(now) }
+    }
+
+    pub fn set(&self, now: i64) {
+        self.now.set(now);
+    }
+
+    pub fn advance(&self, dt: i64) {
+        self.now.set(self.now.get() + dt);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> i64 {
+        self.now.get()
+    }
+}
+
+pub fn system_clock() -> Rc<dyn Clock> {
+    Rc::new(SystemClock)
+}
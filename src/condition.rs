@@ -0,0 +1,630 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Serialize, Deserialize};
+
+use crate::error::GreyGooError;
+use crate::state::State;
+
+// Effect conditions, e.g.
+//
+//     bot_mass > 1e3 and (unease < 50 or has("hive mind"))
+//
+// Conditions are compiled when the game data is loaded. Comparisons
+// involving trial quantities (bot_mass, domination, trial_elapsed) are false
+// while no trial is running.
+
+// A condition as written in the game data (effects, events), compiled as it
+// is read. Game::load reports any that fail to compile.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub struct ConditionSource {
+    pub source: String,
+
+    // None if it failed to parse
+    compiled: Option<Condition>
+}
+
+impl From<String> for ConditionSource {
+    fn from(source: String) -> Self {
+        let compiled = Condition::parse(&source).ok();
+        ConditionSource { source, compiled }
+    }
+}
+
+impl From<ConditionSource> for String {
+    fn from(condition: ConditionSource) -> Self {
+        condition.source
+    }
+}
+
+impl Default for ConditionSource {
+    // No condition at all, which always holds
+    fn default() -> Self {
+        ConditionSource::from(String::new())
+    }
+}
+
+impl ConditionSource {
+    pub fn get(&self) -> Option<&Condition> {
+        self.compiled.as_ref()
+    }
+
+    pub fn evaluate(&self, state: &State) -> Result<bool, GreyGooError> {
+        match &self.compiled {
+            Some(condition) => Ok(condition.evaluate(state)),
+            None => Err(GreyGooError::BadCondition {
+                condition: self.source.clone(),
+                message: "condition was not compiled".to_string()
+            })
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Default)]
+pub enum Condition {
+    #[default]
+    Always,
+    Never,
+    TrialRunning,
+    HasModifier(String),
+    Researching(String),
+    Compare { lhs: Value, op: CompareOp, rhs: Value },
+    Not(Box<Condition>),
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Value {
+    Number(f64),
+    Variable(Variable)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Variable {
+    BotMass,
+    Domination,
+    TrialElapsed,
+    PopulationUnease,
+    ScientificInspiration,
+    ModifiersOwned,
+    ResearchInProgress,
+    ResearchQueued
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CompareOp {
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Equal
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ConditionError {
+    // Character offset into the condition
+    pub position: usize,
+    pub message: String
+}
+
+impl fmt::Display for ConditionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at character {}", self.message, self.position + 1)
+    }
+}
+
+const VARIABLES: [(&str, Variable); 8] = [
+    ("bot_mass", Variable::BotMass),
+    ("domination", Variable::Domination),
+    ("trial_elapsed", Variable::TrialElapsed),
+    ("unease", Variable::PopulationUnease),
+    ("inspiration", Variable::ScientificInspiration),
+    ("modifiers_owned", Variable::ModifiersOwned),
+    ("research_in_progress", Variable::ResearchInProgress),
+    ("research_queued", Variable::ResearchQueued)
+];
+
+impl Variable {
+    pub fn name(&self) -> &'static str {
+        VARIABLES.iter().find(|(_, v)| v == self).map(|(n, _)| *n).unwrap_or("?")
+    }
+
+    // None when the quantity doesn't exist right now, i.e. trial quantities
+    // with no trial running.
+    pub fn evaluate(&self, state: &State) -> Option<f64> {
+        let trial = state.trial_in_progress.as_ref();
+        match self {
+            Variable::BotMass => trial.map(|t| t.bot_mass),
+            Variable::Domination => trial.map(|t| t.bot_mass / state.game.world_mass),
+            Variable::TrialElapsed => trial.map(|t| (t.last_update_ts - t.start_ts) as f64),
+            Variable::PopulationUnease => Some(state.population_unease),
+            Variable::ScientificInspiration => Some(state.scientific_inspiration),
+            Variable::ModifiersOwned => Some(state.active_modifiers.len() as f64),
+            Variable::ResearchInProgress => Some(state.modifiers_in_progress.len() as f64),
+            Variable::ResearchQueued => Some(state.research_queue.len() as f64)
+        }
+    }
+}
+
+impl Value {
+    fn evaluate(&self, state: &State) -> Option<f64> {
+        match self {
+            Value::Number(n) => Some(*n),
+            Value::Variable(v) => v.evaluate(state)
+        }
+    }
+}
+
+impl CompareOp {
+    pub fn apply(&self, lhs: f64, rhs: f64) -> bool {
+        match self {
+            CompareOp::Less => lhs < rhs,
+            CompareOp::LessEqual => lhs <= rhs,
+            CompareOp::Greater => lhs > rhs,
+            CompareOp::GreaterEqual => lhs >= rhs,
+            CompareOp::Equal => lhs == rhs
+        }
+    }
+}
+
+impl Condition {
+    pub fn parse(source: &str) -> Result<Condition, ConditionError> {
+        if source.trim().is_empty() {
+            return Ok(Condition::Always);
+        }
+        if let Some(condition) = parse_legacy(source) {
+            return Ok(condition);
+        }
+
+        let mut parser = Parser { tokens: tokenise(source)?, pos: 0, end: source.chars().count() };
+        let condition = parser.or()?;
+        match parser.peek() {
+            None => Ok(condition),
+            Some((position, token)) => Err(ConditionError {
+                position: *position,
+                message: format!("unexpected {}", token)
+            })
+        }
+    }
+
+    pub fn evaluate(&self, state: &State) -> bool {
+        match self {
+            Condition::Always => true,
+            Condition::Never => false,
+            Condition::TrialRunning => state.trial_in_progress.is_some(),
+            Condition::HasModifier(mod_name) => state.active_modifiers.contains(mod_name),
+            Condition::Researching(mod_name) => state.modifiers_in_progress.iter().any(|r| &r.name == mod_name),
+            Condition::Compare { lhs, op, rhs } => match (lhs.evaluate(state), rhs.evaluate(state)) {
+                (Some(lhs), Some(rhs)) => op.apply(lhs, rhs),
+                _ => false
+            },
+            Condition::Not(c) => !c.evaluate(state),
+            Condition::And(a, b) => a.evaluate(state) && b.evaluate(state),
+            Condition::Or(a, b) => a.evaluate(state) || b.evaluate(state)
+        }
+    }
+
+    // Modifiers named by has(...) and researching(...).
+    pub fn modifier_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        self.walk(&mut |c| match c {
+            Condition::HasModifier(n) | Condition::Researching(n) => names.push(n),
+            _ => ()
+        });
+        names
+    }
+
+    // Comparisons of a variable with a number, as (variable, number). These
+    // are the only comparisons whose outcome can be predicted as the
+    // variable changes.
+    pub fn thresholds(&self) -> Vec<(Variable, f64)> {
+        let mut thresholds: Vec<(Variable, f64)> = Vec::new();
+        self.walk(&mut |c| match c {
+            Condition::Compare { lhs: Value::Variable(v), rhs: Value::Number(n), .. }
+            | Condition::Compare { lhs: Value::Number(n), rhs: Value::Variable(v), .. } => thresholds.push((*v, *n)),
+            _ => ()
+        });
+        thresholds
+    }
+
+    pub fn walk<'a>(&'a self, f: &mut impl FnMut(&'a Condition)) {
+        f(self);
+        match self {
+            Condition::Not(c) => c.walk(f),
+            Condition::And(a, b) | Condition::Or(a, b) => {
+                a.walk(f);
+                b.walk(f);
+            },
+            _ => ()
+        }
+    }
+}
+
+// The phrases conditions were written in before the expression language,
+// e.g. "trial bot mass greater than 1e1".
+fn parse_legacy(source: &str) -> Option<Condition> {
+    let source = source.trim();
+    if let Some(mod_name) = source.strip_prefix("has modifier ") {
+        return Some(Condition::HasModifier(mod_name.to_string()));
+    }
+
+    let (variable, rest) = if let Some(rest) = source.strip_prefix("trial bot mass ") {
+        (Variable::BotMass, rest)
+    }
+    else if let Some(rest) = source.strip_prefix("population unease ") {
+        (Variable::PopulationUnease, rest)
+    }
+    else {
+        return None;
+    };
+
+    let (op, threshold) = if let Some(threshold) = rest.strip_prefix("greater than ") {
+        (CompareOp::Greater, threshold)
+    }
+    else if let Some(threshold) = rest.strip_prefix("less than ") {
+        (CompareOp::Less, threshold)
+    }
+    else {
+        return None;
+    };
+
+    let threshold = f64::from_str(threshold.trim()).ok()?;
+    Some(Condition::Compare { lhs: Value::Variable(variable), op, rhs: Value::Number(threshold) })
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Str(String),
+    Op(CompareOp),
+    LParen,
+    RParen
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Number(n) => write!(f, "number {}", n),
+            Token::Ident(s) => write!(f, "\"{}\"", s),
+            Token::Str(s) => write!(f, "string \"{}\"", s),
+            Token::Op(op) => write!(f, "\"{}\"", match op {
+                CompareOp::Less => "<",
+                CompareOp::LessEqual => "<=",
+                CompareOp::Greater => ">",
+                CompareOp::GreaterEqual => ">=",
+                CompareOp::Equal => "=="
+            }),
+            Token::LParen => write!(f, "\"(\""),
+            Token::RParen => write!(f, "\")\"")
+        }
+    }
+}
+
+fn tokenise(source: &str) -> Result<Vec<(usize, Token)>, ConditionError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens: Vec<(usize, Token)> = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let start = i;
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        let token = if c == '(' {
+            i += 1;
+            Token::LParen
+        }
+        else if c == ')' {
+            i += 1;
+            Token::RParen
+        }
+        else if c == '<' || c == '>' || c == '=' {
+            let or_equal = next == Some('=');
+            i += if or_equal { 2 } else { 1 };
+            Token::Op(match (c, or_equal) {
+                ('<', false) => CompareOp::Less,
+                ('<', true) => CompareOp::LessEqual,
+                ('>', false) => CompareOp::Greater,
+                ('>', true) => CompareOp::GreaterEqual,
+                ('=', true) => CompareOp::Equal,
+                _ => return Err(ConditionError { position: start, message: "expected \"==\"".to_string() })
+            })
+        }
+        else if c == '"' || c == '\'' {
+            i += 1;
+            let mut s = String::new();
+            while i < chars.len() && chars[i] != c {
+                s.push(chars[i]);
+                i += 1;
+            }
+            if i == chars.len() {
+                return Err(ConditionError { position: start, message: "unterminated string".to_string() });
+            }
+            i += 1;
+            Token::Str(s)
+        }
+        else if c.is_ascii_digit() || c == '.' || (c == '-' && next.map_or(false, |n| n.is_ascii_digit() || n == '.')) {
+            i += 1;
+            while i < chars.len() {
+                let d = chars[i];
+                let exponent_sign = (d == '+' || d == '-') && (chars[i - 1] == 'e' || chars[i - 1] == 'E');
+                if d.is_ascii_digit() || d == '.' || d == 'e' || d == 'E' || exponent_sign {
+                    i += 1;
+                }
+                else {
+                    break;
+                }
+            }
+            let text: String = chars[start..i].iter().collect();
+            match f64::from_str(&text) {
+                Ok(n) => Token::Number(n),
+                Err(_) => return Err(ConditionError { position: start, message: format!("bad number \"{}\"", text) })
+            }
+        }
+        else if c.is_alphabetic() || c == '_' {
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            Token::Ident(chars[start..i].iter().collect())
+        }
+        else {
+            return Err(ConditionError { position: start, message: format!("unexpected \"{}\"", c) });
+        };
+
+        tokens.push((start, token));
+    }
+    Ok(tokens)
+}
+
+// Recursive descent, loosest binding first:
+//
+//     or      := and ("or" and)*
+//     and     := unary ("and" unary)*
+//     unary   := "not" unary | primary
+//     primary := "(" or ")" | "true" | "false" | "trial"
+//              | ("has" | "researching") "(" string ")"
+//              | value op value
+//     value   := number | variable
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    // Length of the source, for errors at the end of it
+    end: usize
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&(usize, Token)> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Result<(usize, Token), ConditionError> {
+        match self.tokens.get(self.pos) {
+            Some(t) => {
+                self.pos += 1;
+                Ok(t.clone())
+            },
+            None => Err(ConditionError { position: self.end, message: "unexpected end of condition".to_string() })
+        }
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some((_, Token::Ident(s))) if s == keyword)
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), ConditionError> {
+        let (position, token) = self.next()?;
+        if token == expected {
+            Ok(())
+        }
+        else {
+            Err(ConditionError { position, message: format!("expected {}, found {}", expected, token) })
+        }
+    }
+
+    fn or(&mut self) -> Result<Condition, ConditionError> {
+        let mut lhs = self.and()?;
+        while self.is_keyword("or") {
+            self.pos += 1;
+            lhs = Condition::Or(Box::new(lhs), Box::new(self.and()?));
+        }
+        Ok(lhs)
+    }
+
+    fn and(&mut self) -> Result<Condition, ConditionError> {
+        let mut lhs = self.unary()?;
+        while self.is_keyword("and") {
+            self.pos += 1;
+            lhs = Condition::And(Box::new(lhs), Box::new(self.unary()?));
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Condition, ConditionError> {
+        if self.is_keyword("not") {
+            self.pos += 1;
+            return Ok(Condition::Not(Box::new(self.unary()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Condition, ConditionError> {
+        let (position, token) = self.next()?;
+        match token {
+            Token::LParen => {
+                let condition = self.or()?;
+                self.expect(Token::RParen)?;
+                Ok(condition)
+            },
+            Token::Ident(s) if s == "true" => Ok(Condition::Always),
+            Token::Ident(s) if s == "false" => Ok(Condition::Never),
+            Token::Ident(s) if s == "trial" => Ok(Condition::TrialRunning),
+            Token::Ident(s) if s == "has" || s == "researching" => {
+                self.expect(Token::LParen)?;
+                let mod_name = match self.next()? {
+                    (_, Token::Str(mod_name)) => mod_name,
+                    (position, token) => return Err(ConditionError {
+                        position,
+                        message: format!("expected a quoted modifier name, found {}", token)
+                    })
+                };
+                self.expect(Token::RParen)?;
+                if s == "has" {
+                    Ok(Condition::HasModifier(mod_name))
+                }
+                else {
+                    Ok(Condition::Researching(mod_name))
+                }
+            },
+            token => {
+                let lhs = self.value(position, token)?;
+                let op = match self.next()? {
+                    (_, Token::Op(op)) => op,
+                    (position, token) => return Err(ConditionError {
+                        position,
+                        message: format!("expected a comparison, found {}", token)
+                    })
+                };
+                let (position, token) = self.next()?;
+                let rhs = self.value(position, token)?;
+                Ok(Condition::Compare { lhs, op, rhs })
+            }
+        }
+    }
+
+    fn value(&self, position: usize, token: Token) -> Result<Value, ConditionError> {
+        match token {
+            Token::Number(n) => Ok(Value::Number(n)),
+            Token::Ident(s) => match VARIABLES.iter().find(|(name, _)| *name == s) {
+                Some((_, variable)) => Ok(Value::Variable(*variable)),
+                None => {
+                    let names: Vec<&str> = VARIABLES.iter().map(|(name, _)| *name).collect();
+                    Err(ConditionError {
+                        position,
+                        message: format!("unknown variable \"{}\" (expected one of {})", s, names.join(", "))
+                    })
+                }
+            },
+            token => Err(ConditionError { position, message: format!("expected a number or variable, found {}", token) })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::game::Game;
+
+    fn var(variable: Variable) -> Value {
+        Value::Variable(variable)
+    }
+
+    fn num(n: f64) -> Value {
+        Value::Number(n)
+    }
+
+    fn compare(lhs: Value, op: CompareOp, rhs: Value) -> Condition {
+        Condition::Compare { lhs, op, rhs }
+    }
+
+    fn parse(source: &str) -> Condition {
+        Condition::parse(source).unwrap()
+    }
+
+    fn error(source: &str) -> String {
+        Condition::parse(source).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn parses_numbers() {
+        assert_eq!(parse("bot_mass > 1e+3"), compare(var(Variable::BotMass), CompareOp::Greater, num(1000f64)));
+        assert_eq!(parse("unease>=2.5E-1"), compare(var(Variable::PopulationUnease), CompareOp::GreaterEqual, num(0.25)));
+        assert_eq!(parse("inspiration > -1"), compare(var(Variable::ScientificInspiration), CompareOp::Greater, num(-1f64)));
+        assert_eq!(parse("-.5 < unease"), compare(num(-0.5), CompareOp::Less, var(Variable::PopulationUnease)));
+        assert_eq!(parse("modifiers_owned == 3"), compare(var(Variable::ModifiersOwned), CompareOp::Equal, num(3f64)));
+        assert_eq!(parse("research_queued <= 0"), compare(var(Variable::ResearchQueued), CompareOp::LessEqual, num(0f64)));
+    }
+
+    #[test]
+    fn parses_keywords_and_modifier_tests() {
+        assert_eq!(parse(""), Condition::Always);
+        assert_eq!(parse("  "), Condition::Always);
+        assert_eq!(parse("true"), Condition::Always);
+        assert_eq!(parse("false"), Condition::Never);
+        assert_eq!(parse("trial"), Condition::TrialRunning);
+        assert_eq!(parse("has(\"hive mind\")"), Condition::HasModifier("hive mind".to_string()));
+        assert_eq!(parse("researching('hive mind')"), Condition::Researching("hive mind".to_string()));
+    }
+
+    #[test]
+    fn not_binds_tighter_than_and_than_or() {
+        let not = |c: Condition| Condition::Not(Box::new(c));
+        let and = |a: Condition, b: Condition| Condition::And(Box::new(a), Box::new(b));
+        let or = |a: Condition, b: Condition| Condition::Or(Box::new(a), Box::new(b));
+        let (t, f) = (Condition::Always, Condition::Never);
+
+        assert_eq!(parse("true or false and trial"), or(t.clone(), and(f.clone(), Condition::TrialRunning)));
+        assert_eq!(parse("not true and false"), and(not(t.clone()), f.clone()));
+        assert_eq!(parse("not not trial"), not(not(Condition::TrialRunning)));
+        assert_eq!(parse("(true or false) and trial"), and(or(t.clone(), f.clone()), Condition::TrialRunning));
+        assert_eq!(parse("true or false or trial"), or(or(t, f), Condition::TrialRunning));
+    }
+
+    #[test]
+    fn parses_legacy_phrases() {
+        assert_eq!(parse("trial bot mass greater than 1e1"), compare(var(Variable::BotMass), CompareOp::Greater, num(10f64)));
+        assert_eq!(parse("population unease less than 50"), compare(var(Variable::PopulationUnease), CompareOp::Less, num(50f64)));
+        assert_eq!(parse("has modifier hive mind"), Condition::HasModifier("hive mind".to_string()));
+    }
+
+    #[test]
+    fn reports_where_conditions_go_wrong() {
+        assert_eq!(error("bot_mass >> 3"), "expected a number or variable, found \">\" at character 11");
+        assert_eq!(error("bot_mass = 3"), "expected \"==\" at character 10");
+        assert_eq!(error("bot_mass > 1e"), "bad number \"1e\" at character 12");
+        assert_eq!(error("mass > 3"), "unknown variable \"mass\" (expected one of bot_mass, domination, trial_elapsed, \
+                                      unease, inspiration, modifiers_owned, research_in_progress, research_queued) at character 1");
+        assert_eq!(error("bot_mass > 3 and"), "unexpected end of condition at character 17");
+        assert_eq!(error("(true or false"), "unexpected end of condition at character 15");
+        assert_eq!(error("true false"), "unexpected \"false\" at character 6");
+        assert_eq!(error("has(hive)"), "expected a quoted modifier name, found \"hive\" at character 5");
+        assert_eq!(error("has(\"hive"), "unterminated string at character 5");
+        assert_eq!(error("bot_mass 3"), "expected a comparison, found number 3 at character 10");
+        assert_eq!(error("unease > 3 & trial"), "unexpected \"&\" at character 12");
+    }
+
+    #[test]
+    fn lists_modifier_names_and_thresholds() {
+        let condition = parse("bot_mass > 1e3 and (5 < unease or has(\"a\")) and not researching(\"b\")");
+        assert_eq!(condition.modifier_names(), ["a", "b"]);
+        assert_eq!(condition.thresholds(), [(Variable::BotMass, 1e3), (Variable::PopulationUnease, 5f64)]);
+    }
+
+    #[test]
+    fn trial_comparisons_are_false_without_a_trial() {
+        let mut w = State::new_with_clock(Game::builtin(), Rc::new(ManualClock::new(1_700_000_000)));
+        w.population_unease = 20f64;
+        let holds = |w: &State, source: &str| parse(source).evaluate(w);
+
+        assert!(!holds(&w, "bot_mass > 0"));
+        assert!(!holds(&w, "bot_mass <= 0"));
+        assert!(!holds(&w, "trial_elapsed >= 0"));
+        assert!(holds(&w, "not domination > 0"));
+        assert!(!holds(&w, "trial"));
+        assert!(holds(&w, "unease > 10 and unease < 30"));
+
+        w.start_trial().unwrap();
+        assert!(holds(&w, "bot_mass > 0"));
+        assert!(holds(&w, "trial_elapsed >= 0"));
+        assert!(holds(&w, "trial"));
+    }
+}
@@ -0,0 +1,19 @@
+use serde::Deserialize;
+
+use crate::serde_default_funcs::zero;
+
+#[derive(Deserialize)]
+pub struct Difficulty {
+    #[serde(default)]
+    pub description: String,
+
+    // Starting position
+    #[serde(default)]
+    pub starting_modifiers: Vec<String>,
+
+    #[serde(default="zero")]
+    pub population_unease: f64,
+
+    #[serde(default="zero")]
+    pub scientific_inspiration: f64,
+}
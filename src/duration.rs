@@ -0,0 +1,180 @@
+// Durations in whole seconds, written either as compound units ("1d 12h",
+// "1.5h", "2w3d") or ISO-8601 ("P1DT12H", "PT90M").
+
+const UNITS: [(char, i64); 5] = [
+    ('w', 604800),
+    ('d', 86400),
+    ('h', 3600),
+    ('m', 60),
+    ('s', 1)
+];
+
+pub fn parse_duration(s: &str) -> Option<i64> {
+    let s = s.trim();
+    if s.starts_with('P') || s.starts_with('p') {
+        parse_iso8601(&s[1..])
+    }
+    else {
+        parse_compound(s)
+    }
+}
+
+fn unit_seconds(unit: char) -> Option<i64> {
+    UNITS.iter().find(|(u, _)| *u == unit).map(|(_, seconds)| *seconds)
+}
+
+// Splits "1.5h" style text into (number, unit) pairs, ignoring whitespace
+// between them.
+fn components(s: &str) -> Option<Vec<(f64, char)>> {
+    let mut components: Vec<(f64, char)> = Vec::new();
+    let mut number = String::new();
+    for c in s.chars() {
+        if c.is_ascii_digit() || c == '.' {
+            number.push(c);
+        }
+        else if c.is_whitespace() {
+            if !number.is_empty() {
+                // a number must be followed directly by its unit, or "1 2h"
+                // would read as 12h
+                return None;
+            }
+        }
+        else {
+            let value: f64 = number.parse().ok()?;
+            components.push((value, c.to_ascii_lowercase()));
+            number.clear();
+        }
+    }
+
+    if number.is_empty() && !components.is_empty() {
+        Some(components)
+    }
+    else {
+        None
+    }
+}
+
+fn parse_compound(s: &str) -> Option<i64> {
+    let mut total = 0f64;
+    for (value, unit) in components(s)? {
+        total += value * unit_seconds(unit)? as f64;
+    }
+    Some(total.round() as i64)
+}
+
+fn parse_iso8601(s: &str) -> Option<i64> {
+    let (date, time) = match s.find(['T', 't']) {
+        Some(i) => (&s[..i], Some(&s[i + 1..])),
+        None => (s, None)
+    };
+
+    let mut total = 0f64;
+    if !date.is_empty() {
+        for (value, unit) in components(date)? {
+            // years and months have no fixed length, so aren't accepted
+            match unit {
+                'w' | 'd' => total += value * unit_seconds(unit)? as f64,
+                _ => return None
+            }
+        }
+    }
+    if let Some(time) = time {
+        for (value, unit) in components(time)? {
+            match unit {
+                'h' | 'm' | 's' => total += value * unit_seconds(unit)? as f64,
+                _ => return None
+            }
+        }
+    }
+
+    if date.is_empty() && time.is_none() {
+        None
+    }
+    else {
+        Some(total.round() as i64)
+    }
+}
+
+// Two most significant units, e.g. "1d 12h", "5m", "-3h 20m".
+pub fn format_duration(s: i64) -> String {
+    let sign = if s < 0 { "-" } else { "" };
+    let mut remaining = s.unsigned_abs() as i64;
+
+    let mut parts: Vec<String> = Vec::new();
+    for (unit, seconds) in UNITS {
+        if parts.len() == 2 {
+            break;
+        }
+        let n = remaining / seconds;
+        remaining %= seconds;
+        if n > 0 {
+            parts.push(format!("{}{}", n, unit));
+        }
+        else if !parts.is_empty() {
+            // don't skip a unit, "1d 30s" reads as more precise than it is
+            break;
+        }
+    }
+
+    if parts.is_empty() {
+        "0s".to_string()
+    }
+    else {
+        format!("{}{}", sign, parts.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_compound_durations() {
+        assert_eq!(parse_duration("15m"), Some(900));
+        assert_eq!(parse_duration("1.5h"), Some(5400));
+        assert_eq!(parse_duration("1d 12h"), Some(129600));
+        assert_eq!(parse_duration("2w3d"), Some(1468800));
+        assert_eq!(parse_duration(" 1H 30M "), Some(5400));
+    }
+
+    #[test]
+    fn parses_iso8601_durations() {
+        assert_eq!(parse_duration("PT1H30M"), Some(5400));
+        assert_eq!(parse_duration("P1DT12H"), Some(129600));
+        assert_eq!(parse_duration("P2W"), Some(1209600));
+        assert_eq!(parse_duration("pt90s"), Some(90));
+    }
+
+    #[test]
+    fn rejects_bad_durations() {
+        assert_eq!(parse_duration(""), None);
+        assert_eq!(parse_duration("15"), None);
+        assert_eq!(parse_duration("1 2h"), None);
+        assert_eq!(parse_duration("1 h"), None);
+        assert_eq!(parse_duration("5x"), None);
+        assert_eq!(parse_duration("h"), None);
+        assert_eq!(parse_duration("1.2.3h"), None);
+        assert_eq!(parse_duration("P"), None);
+        assert_eq!(parse_duration("P1Y"), None);
+        assert_eq!(parse_duration("P1M"), None);
+        assert_eq!(parse_duration("PT1D"), None);
+    }
+
+    #[test]
+    fn formats_two_most_significant_units() {
+        assert_eq!(format_duration(0), "0s");
+        assert_eq!(format_duration(45), "45s");
+        assert_eq!(format_duration(300), "5m");
+        assert_eq!(format_duration(129600), "1d 12h");
+        assert_eq!(format_duration(86430), "1d");
+        assert_eq!(format_duration(-12000), "-3h 20m");
+        assert_eq!(format_duration(1468800), "2w 3d");
+    }
+
+    #[test]
+    fn formatted_durations_parse_back() {
+        for s in [60, 5400, 129600, 1468800] {
+            assert_eq!(parse_duration(&format_duration(s)), Some(s));
+        }
+    }
+}
@@ -1,77 +1,218 @@
-use std::str::FromStr;
+use std::collections::HashMap;
+use std::fmt;
 
 use serde::{Serialize, Deserialize};
-use regex::Regex;
+
+use crate::condition::ConditionSource;
+use crate::error::GreyGooError;
 use crate::state::State;
 
 use crate::serde_default_funcs::one;
 
-const COND_HAS_MOD: &str = r"^has modifier (.*)$";
-const COND_TRIAL_BOT_MASS: &str = r"trial bot mass (less|greater) than (\d+(?:\.\d+)?(?:e\d+)?)";
-const COND_POP_UNEASE: &str = r"population unease (less|greater) than (\d+(?:\.\d+)?(?:e\d+)?)";
+#[derive(Clone, Copy, Debug, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Stacking {
+    // Only the highest priority triggered variant applies
+    #[default]
+    Replace,
+    // Applies alongside whichever variant is chosen
+    Combine
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stat {
+    InitialMass,
+    GrowthRate,
+    DeathRate,
+    UneaseGain,
+    InspirationGain,
+    ResearchSpeed,
+    ResearchCost,
+    ResearchSlots,
+    UneaseDecay,
+    InspirationCap,
+    SelfDestructReliability
+}
+
+impl fmt::Display for Stat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Stat::InitialMass => "initial_mass",
+            Stat::GrowthRate => "growth_rate",
+            Stat::DeathRate => "death_rate",
+            Stat::UneaseGain => "unease_gain",
+            Stat::InspirationGain => "inspiration_gain",
+            Stat::ResearchSpeed => "research_speed",
+            Stat::ResearchCost => "research_cost",
+            Stat::ResearchSlots => "research_slots",
+            Stat::UneaseDecay => "unease_decay",
+            Stat::InspirationCap => "inspiration_cap",
+            Stat::SelfDestructReliability => "self_destruct_reliability"
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ResearchScaling {
+    #[serde(default="one")]
+    pub time_mult: f64,
+
+    #[serde(default="one")]
+    pub cost_mult: f64
+}
 
 #[derive(Serialize, Deserialize)]
 pub struct Effect {
-    // Stats
+    // Stats, see apply_effects for how these combine
+    #[serde(default)]
+    pub initial_mass_add: f64,
+
     #[serde(default="one")]
     pub initial_mass_mult: f64,
 
+    #[serde(default)]
+    pub growth_rate_add: f64,
+
     #[serde(default="one")]
     pub growth_rate_mult: f64,
 
+    #[serde(default)]
+    pub death_rate_add: f64,
+
     #[serde(default="one")]
     pub death_rate_mult: f64,
 
+    #[serde(default)]
+    pub unease_gain_add: f64,
+
     #[serde(default="one")]
     pub unease_gain_mult: f64,
 
+    #[serde(default)]
+    pub unease_decay_add: f64,
+
+    #[serde(default="one")]
+    pub unease_decay_mult: f64,
+
+    #[serde(default)]
+    pub inspiration_gain_add: f64,
+
     #[serde(default="one")]
     pub inspiration_gain_mult: f64,
 
     #[serde(default)]
-    pub condition: String
+    pub inspiration_cap_add: f64,
+
+    #[serde(default="one")]
+    pub inspiration_cap_mult: f64,
+
+    #[serde(default)]
+    pub self_destruct_reliability_add: f64,
+
+    #[serde(default="one")]
+    pub self_destruct_reliability_mult: f64,
+
+    // Research
+    #[serde(default)]
+    pub research_speed_add: f64,
+
+    #[serde(default="one")]
+    pub research_speed_mult: f64,
+
+    #[serde(default)]
+    pub research_cost_add: f64,
+
+    #[serde(default="one")]
+    pub research_cost_mult: f64,
+
+    #[serde(default)]
+    pub research_slots_add: isize,
+
+    // Scaling of research into modifiers with a given tag, on top of
+    // research speed and cost, e.g. "research_by_tag": {"nanotech": {"time_mult": 0.5}}
+    #[serde(default)]
+    pub research_by_tag: HashMap<String, ResearchScaling>,
+
+    // Clamping, e.g. "max": {"growth_rate": 2.0}
+    #[serde(default)]
+    pub min: HashMap<Stat, f64>,
+
+    #[serde(default)]
+    pub max: HashMap<Stat, f64>,
+
+    // Selection, see Modifier::get_effects
+    #[serde(default)]
+    pub condition: ConditionSource,
+
+    #[serde(default)]
+    pub priority: i64,
+
+    #[serde(default)]
+    pub stacking: Stacking
+}
+
+// The value of a stat given its base value and every effect that applies.
+//
+// All the additions from every effect are made first, then the result is
+// multiplied by all the multipliers, then clamped between the largest "min"
+// and the smallest "max" (the "max" wins if they overlap).
+pub fn apply_effects(stat: Stat, base: f64, effects: &[&Effect]) -> f64 {
+    let mut value = base;
+    for effect in effects {
+        value += effect.get_add(stat);
+    }
+    for effect in effects {
+        value *= effect.get_mult(stat);
+    }
+    for effect in effects {
+        if let Some(min) = effect.min.get(&stat) {
+            value = value.max(*min);
+        }
+    }
+    for effect in effects {
+        if let Some(max) = effect.max.get(&stat) {
+            value = value.min(*max);
+        }
+    }
+    value
 }
 
 impl Effect {
-    pub fn is_triggered(&self, state: &State) -> bool {
-        if self.condition.is_empty() {
-            true
+    pub fn get_add(&self, stat: Stat) -> f64 {
+        match stat {
+            Stat::InitialMass => self.initial_mass_add,
+            Stat::GrowthRate => self.growth_rate_add,
+            Stat::DeathRate => self.death_rate_add,
+            Stat::UneaseGain => self.unease_gain_add,
+            Stat::InspirationGain => self.inspiration_gain_add,
+            Stat::ResearchSpeed => self.research_speed_add,
+            Stat::ResearchCost => self.research_cost_add,
+            Stat::ResearchSlots => self.research_slots_add as f64,
+            Stat::UneaseDecay => self.unease_decay_add,
+            Stat::InspirationCap => self.inspiration_cap_add,
+            Stat::SelfDestructReliability => self.self_destruct_reliability_add
         }
-        else {
-            if let Some(c) = Regex::new(COND_HAS_MOD).expect("COND_HAS_MOD").captures(&self.condition) {
-                state.active_modifiers.contains(&c[1].to_string())
-            }
-            else if let Some(c) = Regex::new(COND_TRIAL_BOT_MASS).expect("COND_TRIAL_BOT_MASS").captures(&self.condition) {
-                if let Some(trial) = &state.trial_in_progress {
-                    let cond_op_is_greater = c[1].eq("greater");
-                    let cond_bot_mass = f64::from_str(&c[2]).expect("COND_TRIAL_BOT_MASS float parse fail");
-
-                    if cond_op_is_greater {
-                        trial.bot_mass > cond_bot_mass
-                    }
-                    else {
-                        trial.bot_mass < cond_bot_mass
-                    }
-                }
-                else {
-                    false
-                }
-            }
-            else if let Some(c) = Regex::new(COND_POP_UNEASE).expect("COND_POP_UNEASE").captures(&self.condition) {
-                let cond_op_is_greater = c[1].eq("greater");
-                let cond_unease = f64::from_str(&c[2]).expect("COND_POP_UNEASE float parse fail");
-
-                if cond_op_is_greater {
-                    state.population_unease > cond_unease
-                }
-                else {
-                    state.population_unease < cond_unease
-                }
-
-            }
-            else {
-                false
-            }
+    }
+
+    pub fn get_mult(&self, stat: Stat) -> f64 {
+        match stat {
+            Stat::InitialMass => self.initial_mass_mult,
+            Stat::GrowthRate => self.growth_rate_mult,
+            Stat::DeathRate => self.death_rate_mult,
+            Stat::UneaseGain => self.unease_gain_mult,
+            Stat::InspirationGain => self.inspiration_gain_mult,
+            Stat::ResearchSpeed => self.research_speed_mult,
+            Stat::ResearchCost => self.research_cost_mult,
+            Stat::ResearchSlots => 1f64,
+            Stat::UneaseDecay => self.unease_decay_mult,
+            Stat::InspirationCap => self.inspiration_cap_mult,
+            Stat::SelfDestructReliability => self.self_destruct_reliability_mult
         }
     }
-}
\ No newline at end of file
+
+    pub fn is_triggered(&self, state: &State) -> Result<bool, GreyGooError> {
+        self.condition.evaluate(state)
+    }
+}
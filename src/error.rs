@@ -0,0 +1,93 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub enum GreyGooError {
+    // Files
+    Io { path: PathBuf, source: io::Error },
+    Json { path: PathBuf, line: usize, column: usize, message: String },
+    SaveExists(PathBuf),
+    NoSave(PathBuf),
+
+    // Game data
+    BadTimeCost { modifier: String, time_cost: String },
+    BadCondition { condition: String, message: String },
+    BadEventInterval { event: String, mean_interval: String },
+    UnknownModifier(String),
+    UnknownDifficulty { name: String, available: Vec<String> },
+    InvalidGame { issues: usize },
+
+    // Profiles
+    BadProfileName(String),
+    ProfileExists(String),
+    NoSuchProfile(String),
+    ProfileIsActive(String),
+}
+
+impl fmt::Display for GreyGooError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GreyGooError::Io { path, source } => match source.kind() {
+                io::ErrorKind::NotFound => write!(f, "\"{}\" does not exist", path.display()),
+                io::ErrorKind::PermissionDenied => write!(f, "permission denied for \"{}\"", path.display()),
+                _ => write!(f, "could not access \"{}\": {}", path.display(), source)
+            },
+            GreyGooError::Json { path, line, column, message } =>
+                write!(f, "\"{}\" is not valid (line {}, column {}): {}", path.display(), line, column, message),
+            GreyGooError::SaveExists(path) =>
+                write!(f, "a save already exists at \"{}\"", path.display()),
+            GreyGooError::NoSave(path) =>
+                write!(f, "no save found at \"{}\"", path.display()),
+            GreyGooError::BadTimeCost { modifier, time_cost } =>
+                write!(f, "modifier \"{}\" has time cost \"{}\", expected something like \"15m\", \"1d 12h\" or \"PT1H30M\"", modifier, time_cost),
+            GreyGooError::BadCondition { condition, message } =>
+                write!(f, "could not understand condition \"{}\": {}", condition, message),
+            GreyGooError::BadEventInterval { event, mean_interval } =>
+                write!(f, "event \"{}\" has mean interval \"{}\", expected a positive duration like \"6h\"", event, mean_interval),
+            GreyGooError::UnknownModifier(name) =>
+                write!(f, "no such modifier \"{}\"", name),
+            GreyGooError::UnknownDifficulty { name, available } =>
+                write!(f, "no such difficulty \"{}\" (available: {})", name, available.join(", ")),
+            GreyGooError::InvalidGame { issues } =>
+                write!(f, "game data has {} problem(s)", issues),
+            GreyGooError::BadProfileName(name) =>
+                write!(f, "invalid profile name \"{}\" (use letters, digits, '-' and '_')", name),
+            GreyGooError::ProfileExists(name) =>
+                write!(f, "profile \"{}\" already exists", name),
+            GreyGooError::NoSuchProfile(name) =>
+                write!(f, "no such profile \"{}\"", name),
+            GreyGooError::ProfileIsActive(name) =>
+                write!(f, "cannot delete the active profile \"{}\", switch to another profile first", name),
+        }
+    }
+}
+
+impl std::error::Error for GreyGooError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            GreyGooError::Io { source, .. } => Some(source),
+            _ => None
+        }
+    }
+}
+
+impl GreyGooError {
+    pub fn io(path: impl Into<PathBuf>, source: io::Error) -> GreyGooError {
+        GreyGooError::Io { path: path.into(), source }
+    }
+
+    pub fn json(path: impl Into<PathBuf>, source: serde_json::Error) -> GreyGooError {
+        // serde_json appends the position to its message, but we report it separately
+        let mut message = source.to_string();
+        if let Some(i) = message.rfind(" at line ") {
+            message.truncate(i);
+        }
+        GreyGooError::Json {
+            path: path.into(),
+            line: source.line(),
+            column: source.column(),
+            message
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use serde::Deserialize;
+
+use crate::condition::ConditionSource;
+use crate::duration::parse_duration;
+use crate::error::GreyGooError;
+use crate::serde_default_funcs::one;
+
+// Something that can happen during a trial, e.g. a news report about
+// strange grey dust. Events fire at random, on average once every
+// `mean_interval` of trial time while their condition holds.
+#[derive(Deserialize)]
+pub struct Event {
+    // Filled in from the key in the game data
+    #[serde(skip)]
+    pub name: String,
+
+    pub description: String,
+
+    mean_interval: String,
+
+    #[serde(default)]
+    pub condition: ConditionSource,
+
+    #[serde(default)]
+    pub outcome: EventOutcome
+}
+
+#[derive(Clone, Deserialize)]
+pub struct EventOutcome {
+    #[serde(default="one")]
+    pub bot_mass_mult: f64,
+
+    #[serde(default)]
+    pub unease_add: f64,
+
+    #[serde(default)]
+    pub inspiration_add: f64,
+
+    // Gives a level of the modifier for free
+    #[serde(default)]
+    pub grant_modifier: Option<String>,
+
+    // Locks the modifier out for the rest of the game
+    #[serde(default)]
+    pub block_modifier: Option<String>,
+
+    #[serde(default)]
+    pub end_trial: bool
+}
+
+impl Default for EventOutcome {
+    fn default() -> Self {
+        EventOutcome {
+            bot_mass_mult: 1f64,
+            unease_add: 0f64,
+            inspiration_add: 0f64,
+            grant_modifier: None,
+            block_modifier: None,
+            end_trial: false
+        }
+    }
+}
+
+impl Event {
+    pub fn get_mean_interval(&self) -> Result<i64, GreyGooError> {
+        match parse_duration(&self.mean_interval) {
+            Some(t) if t > 0 => Ok(t),
+            _ => Err(GreyGooError::BadEventInterval {
+                event: self.name.clone(),
+                mean_interval: self.mean_interval.clone()
+            })
+        }
+    }
+}
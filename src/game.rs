@@ -1,20 +1,122 @@
 use std::collections::HashMap;
+use std::path::Path;
 
 use serde::Deserialize;
 
+use crate::condition::Condition;
+use crate::difficulty::Difficulty;
+use crate::error::GreyGooError;
+use crate::event::Event;
 use crate::modifier::Modifier;
 use crate::read_file_contents::get_contents;
+use crate::serde_default_funcs::{half, one_slot, ten};
+use crate::unease::UneaseBand;
+
+const BUILTIN_GAME: &str = include_str!("../game.json");
 
 #[derive(Deserialize)]
 pub struct Game {
     pub world_mass: f64,
     pub modifiers: HashMap<String, Modifier>,
-    pub tau: f64
+    pub tau: f64,
+
+    #[serde(default="one_slot")]
+    pub research_slots: usize,
+
+    // Fraction of the inspiration spent on research that is refunded when
+    // it is cancelled, pro-rated by the research time remaining.
+    #[serde(default="half")]
+    pub cancel_refund_fraction: f64,
+
+    // Base unease lost per tau while no trial is running
+    #[serde(default)]
+    pub unease_decay: f64,
+
+    // Base cap on scientific inspiration, uncapped if missing
+    #[serde(default)]
+    pub inspiration_cap: Option<f64>,
+
+    // Unease caused by stopping a trial when every bot escapes the
+    // self-destruct, scaled by the fraction that escape
+    #[serde(default="ten")]
+    pub escape_unease: f64,
+
+    #[serde(default)]
+    pub difficulties: HashMap<String, Difficulty>,
+
+    // Things that can happen during trials
+    #[serde(default)]
+    pub events: HashMap<String, Event>,
+
+    // Consequences of population unease, lowest threshold first
+    #[serde(default)]
+    pub unease_bands: Vec<UneaseBand>
+}
+
+impl Game {
+    pub fn load<P: AsRef<Path>>(filename: P) -> Result<Game, GreyGooError> {
+        let game = Game::load_unchecked(filename)?;
+        game.check_conditions()?;
+        Ok(game)
+    }
+
+    // Loads game data even if some effect or event conditions don't compile, leaving
+    // them for validate_game to report.
+    pub fn load_unchecked<P: AsRef<Path>>(filename: P) -> Result<Game, GreyGooError> {
+        let filename = filename.as_ref();
+        let game_source = get_contents(filename)?;
+        Game::parse(&game_source).map_err(|e| GreyGooError::json(filename, e))
+    }
+
+    pub fn builtin() -> Game {
+        let game = Game::parse(BUILTIN_GAME).expect("built-in game data is invalid");
+        game.check_conditions().expect("built-in game data is invalid");
+        game
+    }
+
+    fn parse(source: &str) -> Result<Game, serde_json::Error> {
+        let mut game: Game = serde_json::from_str(source)?;
+        for (name, modifier) in game.modifiers.iter_mut() {
+            modifier.name = name.clone();
+        }
+        for (name, event) in game.events.iter_mut() {
+            event.name = name.clone();
+        }
+        game.unease_bands.sort_by(|a, b| a.threshold.total_cmp(&b.threshold));
+        Ok(game)
+    }
+
+    // First effect or event condition that doesn't parse or names an
+    // unknown modifier.
+    fn check_conditions(&self) -> Result<(), GreyGooError> {
+        let mut conditions: Vec<(String, &String)> = Vec::new();
+        for (mod_name, modifier) in &self.modifiers {
+            for (effect_name, effect) in &modifier.effects {
+                conditions.push((format!("modifier \"{}\", effect \"{}\"", mod_name, effect_name), &effect.condition.source));
+            }
+        }
+        for (event_name, event) in &self.events {
+            conditions.push((format!("event \"{}\"", event_name), &event.condition.source));
+        }
+        conditions.sort();
+
+        for (location, source) in conditions {
+            let bad_condition = |message: String| GreyGooError::BadCondition {
+                condition: source.clone(),
+                message: format!("{} ({})", message, location)
+            };
+            let condition = Condition::parse(source)
+                .map_err(|e| bad_condition(e.to_string()))?;
+            if let Some(unknown) = condition.modifier_names().into_iter().find(|n| !self.modifiers.contains_key(*n)) {
+                return Err(bad_condition(format!("unknown modifier \"{}\"", unknown)));
+            }
+        }
+        Ok(())
+    }
 }
 
 impl Default for Game {
     fn default() -> Self {
-        let game_source = get_contents("game.json").unwrap();
-        serde_json::from_str(&game_source).unwrap()
+        Game::builtin()
     }
-}
\ No newline at end of file
+}
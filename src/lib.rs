@@ -0,0 +1,33 @@
+//! GreyGoo game engine. Operations on `State` return outcome values rather
+//! than printing, so any front-end can present them.
+
+pub mod away;
+pub mod clock;
+pub mod condition;
+pub mod difficulty;
+pub mod duration;
+pub mod effect;
+pub mod error;
+pub mod event;
+pub mod fmt_mass;
+pub mod fmt_t;
+pub mod game;
+pub mod log;
+pub mod modifier;
+pub mod outcome;
+pub mod paths;
+pub mod prerequisite;
+pub mod profile;
+pub mod research;
+pub mod rng;
+pub mod schedule;
+pub mod state;
+pub mod tree;
+pub mod trial;
+pub mod unease;
+pub mod unlock;
+pub mod validate;
+
+mod read_file_contents;
+mod serde_default_funcs;
+mod write_file_contents;
@@ -0,0 +1,88 @@
+use serde::{Deserialize, Serialize};
+
+use crate::outcome::TrialOutcome;
+use crate::state::State;
+
+// Oldest entries are dropped beyond this, to keep saves small.
+pub const LOG_CAPACITY: usize = 1000;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LogEntry {
+    pub ts: i64,
+
+    #[serde(flatten)]
+    pub event: LogEvent
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LogEvent {
+    ResearchStarted { name: String, inspiration_spent: f64 },
+    ResearchCompleted { name: String },
+    ResearchCancelled { name: String, was_queued: bool, refund: f64 },
+    TrialStarted { initial_bot_mass: f64 },
+    TrialEnded { outcome: TrialOutcome, bot_mass: f64, escaped_bot_mass: f64 },
+    TrialEvent { name: String, description: String },
+    UneaseConsequence { name: String, description: String }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LogKind {
+    Research,
+    Trial,
+    Event
+}
+
+impl LogKind {
+    pub fn parse(s: &str) -> Option<LogKind> {
+        match s {
+            "research" => Some(LogKind::Research),
+            "trial" => Some(LogKind::Trial),
+            "event" => Some(LogKind::Event),
+            _ => None
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LogKind::Research => "research",
+            LogKind::Trial => "trial",
+            LogKind::Event => "event"
+        }
+    }
+}
+
+impl LogEvent {
+    pub fn kind(&self) -> LogKind {
+        match self {
+            LogEvent::ResearchStarted { .. }
+            | LogEvent::ResearchCompleted { .. }
+            | LogEvent::ResearchCancelled { .. } => LogKind::Research,
+            LogEvent::TrialStarted { .. }
+            | LogEvent::TrialEnded { .. } => LogKind::Trial,
+            LogEvent::TrialEvent { .. }
+            | LogEvent::UneaseConsequence { .. } => LogKind::Event
+        }
+    }
+}
+
+impl State {
+    // Kept in time order, as catching up can record things that happened
+    // before the latest entry.
+    pub(crate) fn record(&mut self, ts: i64, event: LogEvent) {
+        let i = self.log.partition_point(|e| e.ts <= ts);
+        self.log.insert(i, LogEntry { ts, event });
+        if self.log.len() > LOG_CAPACITY {
+            let excess = self.log.len() - LOG_CAPACITY;
+            self.log.drain(..excess);
+        }
+    }
+
+    // Entries at or after since_ts, optionally of one kind, oldest first.
+    pub fn query_log(&self, since_ts: Option<i64>, kind: Option<LogKind>) -> Vec<&LogEntry> {
+        self.log.iter()
+            .filter(|e| since_ts.map_or(true, |ts| e.ts >= ts))
+            .filter(|e| kind.map_or(true, |k| e.event.kind() == k))
+            .collect()
+    }
+}
@@ -1,23 +1,107 @@
-use argparse::{Store, StoreTrue};
-
-mod trial;
-mod state;
-mod modifier;
-mod read_file_contents;
-mod write_file_contents;
-mod game;
-mod fmt_t;
-mod effect;
-mod serde_default_funcs;
-mod fmt_mass;
+use std::io::{stdout, stderr};
+use std::process::exit;
+
+use argparse::{List, Store, StoreTrue};
+
+use greygoo::{duration, game, paths, profile, state, validate};
+use greygoo::log::LogKind;
+use greygoo::error::GreyGooError;
+
+mod render;
+
+fn load_game(game_path: &str, checked: bool) -> Result<game::Game, GreyGooError> {
+    match paths::resolve_game_path(game_path) {
+        Some(game_path) if checked => game::Game::load(game_path),
+        Some(game_path) => game::Game::load_unchecked(game_path),
+        None => Ok(game::Game::builtin())
+    }
+}
+
+fn profile_command(mut args: Vec<String>, game: game::Game) -> Result<(), GreyGooError> {
+    let mut action = String::new();
+    let mut name = String::new();
+    {
+        let mut parser = argparse::ArgumentParser::new();
+        parser.set_description("Manage save profiles.");
+        parser.refer(&mut action).required()
+            .add_argument("action", Store,
+                          "One of \"new\", \"list\", \"switch\" or \"delete\".");
+        parser.refer(&mut name)
+            .add_argument("name", Store,
+                          "Name of the profile (not needed for \"list\").");
+        args.insert(0, "greygoo profile".to_string());
+        if let Err(code) = parser.parse(args, &mut stdout(), &mut stderr()) {
+            exit(code);
+        }
+    }
+
+    if action != "list" && name.is_empty() {
+        eprintln!("Profile name required for \"{}\".", action);
+        exit(2);
+    }
+
+    match action.as_str() {
+        "list" => {
+            let active = profile::active_profile();
+            let profiles = profile::list_profiles();
+            if profiles.is_empty() {
+                println!("No profiles.");
+            }
+            for name in profiles {
+                let marker = if name == active { "*" } else { " " };
+                println!("{} {}", marker, name);
+            }
+        },
+        "new" => {
+            profile::new_profile(&name, game)?;
+            println!("Created profile \"{}\".", name);
+        },
+        "switch" => {
+            profile::switch_profile(&name)?;
+            println!("Switched to profile \"{}\".", name);
+        },
+        "delete" => {
+            profile::delete_profile(&name)?;
+            println!("Deleted profile \"{}\".", name);
+        },
+        _ => {
+            eprintln!("Unknown profile action \"{}\".", action);
+            exit(2);
+        }
+    }
+    Ok(())
+}
 
 fn main() {
+    if let Err(e) = run() {
+        render::error(&e);
+        exit(1);
+    }
+}
+
+fn run() -> Result<(), GreyGooError> {
     // options
     let mut should_check = false;
     let mut should_list = false;
     let mut what_to_research = String::new();
+    let mut what_to_queue = String::new();
+    let mut what_to_cancel = String::new();
     let mut should_do_trial = false;
     let mut should_cancel_trial = false;
+    let mut save_path = String::new();
+    let mut game_path = String::new();
+    let mut profile_name = String::new();
+    let mut should_start_new_game = false;
+    let mut difficulty = String::new();
+    let mut force = false;
+    let mut should_validate_game = false;
+    let mut should_show_tree = false;
+    let mut should_show_log = false;
+    let mut log_since = String::new();
+    let mut log_kind = String::new();
+    let mut tree_export_format = String::new();
+    let mut command = String::new();
+    let mut command_args: Vec<String> = Vec::new();
     {
         let mut parser = argparse::ArgumentParser::new();
         parser.refer(&mut should_check)
@@ -29,42 +113,198 @@ fn main() {
         parser.refer(&mut what_to_research)
             .add_option(&["-r", "--do-research"], Store,
                         "Conduct research");
+        parser.refer(&mut what_to_queue)
+            .add_option(&["-q", "--queue-research"], Store,
+                        "Queue research, to start as soon as a lab slot is free and its prerequisites are met.");
+        parser.refer(&mut what_to_cancel)
+            .add_option(&["--cancel-research"], Store,
+                        "Cancel queued or in-progress research. Part of the inspiration spent is refunded, less the longer it has been running.");
+        parser.refer(&mut should_show_tree)
+            .add_option(&["--tree"], StoreTrue,
+                        "Show the whole research tree, marking what is owned, in progress, available or locked.");
+        parser.refer(&mut tree_export_format)
+            .add_option(&["--export-tree"], Store,
+                        "Print the research tree in the given format (only \"dot\", for Graphviz, is supported).");
+        parser.refer(&mut should_show_log)
+            .add_option(&["--log"], StoreTrue,
+                        "Show the log of research, trials and events.");
+        parser.refer(&mut log_since)
+            .add_option(&["--since"], Store,
+                        "With --log, only show entries from this long ago onwards, e.g. \"2d\" or \"1d 12h\".");
+        parser.refer(&mut log_kind)
+            .add_option(&["--kind"], Store,
+                        "With --log, only show entries of this kind: \"research\", \"trial\" or \"event\".");
         parser.refer(&mut should_do_trial)
             .add_option(&["-t", "--do-trial"], StoreTrue,
                         "Conduct trial with current state-of-the-art robots.");
         parser.refer(&mut should_cancel_trial)
             .add_option(&["-x", "--stop-trial"], StoreTrue,
                         "Cancel a currently running trial, activating the self-destruct of any active bots. Can be combined with --do-trial to effectively restart a trial.");
+        parser.refer(&mut save_path)
+            .add_option(&["--save"], Store,
+                        "Save file to use, instead of the save of the active profile.");
+        parser.refer(&mut profile_name)
+            .add_option(&["-p", "--profile"], Store,
+                        "Use this profile instead of the active profile, without switching to it.");
+        parser.refer(&mut game_path)
+            .add_option(&["--game"], Store,
+                        "Game data file to use. Defaults to game.json in the data directory if present, otherwise the built-in game data.");
+        parser.refer(&mut should_start_new_game)
+            .add_option(&["-n", "--new-game"], StoreTrue,
+                        "Start a new game in the current profile (or --save file). Refuses to overwrite an existing save unless --force is given.");
+        parser.refer(&mut difficulty)
+            .add_option(&["-d", "--difficulty"], Store,
+                        "Difficulty to use with --new-game, as defined in the game data.");
+        parser.refer(&mut force)
+            .add_option(&["-f", "--force"], StoreTrue,
+                        "Allow --new-game to overwrite an existing save.");
+        parser.refer(&mut should_validate_game)
+            .add_option(&["--validate-game"], StoreTrue,
+                        "Check the game data for problems, and report them all.");
+        parser.refer(&mut command)
+            .add_argument("command", Store,
+                          "Optional command: \"profile new|list|switch|delete [name]\" manages save profiles, kept in the data directory ($GREYGOO_HOME, or $XDG_DATA_HOME/greygoo).");
+        parser.refer(&mut command_args)
+            .add_argument("arguments", List,
+                          "Arguments for command.");
+        parser.stop_on_first_argument(true);
         parser.parse_args_or_exit();
     }
+
+    // The validator reports every problem, rather than stopping at the first
+    let game = load_game(&game_path, !should_validate_game)?;
+
+    if should_validate_game {
+        let issues = validate::validate_game(&game);
+        render::validation_issues(&issues);
+        if issues.is_empty() {
+            return Ok(());
+        }
+        return Err(GreyGooError::InvalidGame { issues: issues.len() });
+    }
+
+    match command.as_str() {
+        "" => (),
+        "profile" => {
+            return profile_command(command_args, game);
+        },
+        _ => {
+            eprintln!("Unknown command \"{}\".", command);
+            exit(2);
+        }
+    }
+
     let should_research = !what_to_research.is_empty();
+    let should_queue = !what_to_queue.is_empty();
+    let should_cancel_research = !what_to_cancel.is_empty();
+    let should_export_tree = !tree_export_format.is_empty();
+
+    if should_export_tree && tree_export_format != "dot" {
+        eprintln!("Unknown tree export format \"{}\", expected \"dot\".", tree_export_format);
+        exit(2);
+    }
 
-    if !(should_research || should_check || should_list || should_do_trial) {
+    let log_since = if log_since.is_empty() {
+        None
+    }
+    else {
+        match duration::parse_duration(&log_since) {
+            Some(since) => Some(since),
+            None => {
+                eprintln!("Could not understand --since \"{}\", expected a duration like \"2d\".", log_since);
+                exit(2);
+            }
+        }
+    };
+
+    let log_kind = if log_kind.is_empty() {
+        None
+    }
+    else {
+        match LogKind::parse(&log_kind) {
+            Some(kind) => Some(kind),
+            None => {
+                eprintln!("Unknown log kind \"{}\", expected \"research\", \"trial\" or \"event\".", log_kind);
+                exit(2);
+            }
+        }
+    };
+
+    if !(should_research || should_queue || should_cancel_research || should_check || should_list || should_do_trial
+         || should_show_tree || should_export_tree || should_show_log) {
         should_check = true;
     }
 
-    let mut w = state::State::load("test.json");
+    let save_path = profile::resolve_save_path(&save_path, &profile_name)?;
+
+    let mut w = if should_start_new_game {
+        if save_path.exists() && !force {
+            return Err(GreyGooError::SaveExists(save_path));
+        }
+        let w = state::State::new_game(game, &difficulty)?;
+        match w.game.difficulties.get(&difficulty) {
+            Some(d) => println!("New game started on {}. {}", difficulty, d.description),
+            None => println!("New game started.")
+        }
+        w
+    }
+    else {
+        state::State::load(&save_path, game)?
+    };
 
     if should_list {
-        w.list_potential_modifiers();
+        render::potential_modifiers(&w.list_potential_modifiers()?, w.scientific_inspiration);
+    }
+
+    if should_show_tree {
+        render::research_tree_ascii(&w.research_tree()?);
+    }
+
+    if should_export_tree {
+        render::research_tree_dot(&w.research_tree()?);
     }
 
     if should_check {
-        w.check_research_progress(true);
-        w.check_trial_progress(true);
+        if let Some(summary) = w.away_summary() {
+            render::away_summary(&summary);
+        }
+        render::research_progress(&w.check_research_progress(), &w.research_queue, w.scientific_inspiration);
+        render::trial_report(&w.check_trial_progress());
+        render::population_unease(w.population_unease, w.unease_band());
+    }
+
+    if should_show_log {
+        let since_ts = log_since.map(|since| w.clock.now() - since);
+        render::log_entries(&w.query_log(since_ts, log_kind));
+    }
+
+    if should_cancel_research {
+        render::research_cancelled(&what_to_cancel, &w.cancel_research(&what_to_cancel)?);
     }
 
     if should_research {
-        w.try_research_modifier(what_to_research.as_str());
+        render::research_outcome(&w.try_research_modifier(what_to_research.as_str())?);
+    }
+
+    if should_queue {
+        render::research_outcome(&w.queue_research_modifier(what_to_queue.as_str())?);
     }
 
     if should_cancel_trial {
-        w.stop_trial()
+        match w.stop_trial()? {
+            Some(ended) => render::trial_ended(&ended),
+            None => println!("No active trial to stop.")
+        }
     }
 
     if should_do_trial {
-        w.start_trial();
+        render::trial_start(&w.start_trial()?);
+    }
+
+    // After any actions, so the next summary starts from what they left
+    if should_check {
+        w.mark_seen();
     }
 
-    w.save("test.json");
+    w.save(&save_path)
 }
@@ -1,15 +1,20 @@
-use std::str::FromStr;
 use std::collections::HashMap;
 
 use serde::Deserialize;
-use regex::Regex;
 
-use crate::serde_default_funcs::zero;
-use crate::effect::Effect;
+use crate::duration::parse_duration;
+use crate::error::GreyGooError;
+use crate::serde_default_funcs::{one_level, zero};
+use crate::effect::{Effect, Stacking};
+use crate::prerequisite::Prerequisite;
 use crate::state::State;
 
 #[derive(Deserialize)]
 pub struct Modifier {
+    // Filled in from the key in the game data
+    #[serde(skip)]
+    pub name: String,
+
     pub description: String,
 
     pub effects: HashMap<String, Effect>,
@@ -20,43 +25,68 @@ pub struct Modifier {
     #[serde(default="zero")]
     pub inspiration_cost: f64,
 
+    // For research effects that only apply to some modifiers
+    #[serde(default)]
+    pub tags: Vec<String>,
+
     // Prerequisites
     #[serde(default)]
-    pub locked_by: Vec<String>,
+    pub locked_by: Vec<Prerequisite>,
+
+    // How many times this can be researched. Every level owned applies the
+    // modifier's effect again, so levels stack multiplicatively.
+    #[serde(default="one_level")]
+    pub max_level: usize,
 }
 
 impl Modifier {
-    pub fn get_time_cost(&self) -> i64 {
-        let re = Regex::new(r"(\d+)([wdhms])").unwrap();
-        if let Some(c) = re.captures(self.time_cost.as_str()) {
-            let t = i64::from_str(&c[1]).unwrap();
-            let unit: i64 = match &c[2] {
-                "w" => 604800,
-                "d" => 86400,
-                "h" => 3600,
-                "m" => 60,
-                _ => 1
-            };
-            t*unit
-        }
-        else {
-            panic!("time cost not in expected format! Should be \"\\d+[wdhms]\", but got \"{}\".", self.time_cost);
+    pub fn get_time_cost(&self) -> Result<i64, GreyGooError> {
+        match parse_duration(&self.time_cost) {
+            Some(t) if t >= 0 => Ok(t),
+            _ => Err(GreyGooError::BadTimeCost {
+                modifier: self.name.clone(),
+                time_cost: self.time_cost.clone()
+            })
         }
     }
 
-    pub fn get_effect(&self, state: &State) -> Option<&Effect> {
-        let mut rv: Option<&Effect> = self.effects.get("default");
+    // Effects that apply for the current state, in the order they apply.
+    //
+    // Of the triggered variants with "replace" stacking, the one with the
+    // highest priority is chosen, ties going to the name that sorts first.
+    // If none is triggered, "default" is used. Triggered variants with
+    // "combine" stacking are then applied as well, in the same order.
+    pub fn get_effects(&self, state: &State) -> Result<Vec<&Effect>, GreyGooError> {
+        let mut variants: Vec<(&String, &Effect)> = self.effects.iter()
+            .filter(|(name, _)| *name != "default")
+            .collect();
+        variants.sort_by(|(a_name, a), (b_name, b)| b.priority.cmp(&a.priority).then(a_name.cmp(b_name)));
 
-        let mut effects: Vec<&String> = (&self.effects).into_iter().map(|(s, _)| s).collect();
-        effects.retain(|s| s.ne(&"default"));
-        effects.sort();
-
-        for (name, effect) in &self.effects {
-            if effect.is_triggered(state) {
-                rv = Some(effect);
+        let mut chosen: Option<&Effect> = None;
+        let mut combined: Vec<&Effect> = Vec::new();
+        for (_, effect) in variants {
+            if !effect.is_triggered(state)? {
+                continue;
+            }
+            match effect.stacking {
+                Stacking::Replace if chosen.is_none() => chosen = Some(effect),
+                Stacking::Replace => (),
+                Stacking::Combine => combined.push(effect)
             }
         }
 
-        rv
+        let mut effects: Vec<&Effect> = Vec::new();
+        match chosen {
+            Some(effect) => effects.push(effect),
+            None => {
+                if let Some(effect) = self.effects.get("default") {
+                    if effect.is_triggered(state)? {
+                        effects.push(effect);
+                    }
+                }
+            }
+        }
+        effects.extend(combined);
+        Ok(effects)
     }
 }
\ No newline at end of file
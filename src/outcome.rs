@@ -0,0 +1,115 @@
+// Results of player actions, for a front-end to present however it likes.
+
+use serde::{Deserialize, Serialize};
+
+use crate::unlock::LockExplanation;
+
+pub enum ResearchOutcome {
+    Started(ResearchStarted),
+    Queued(ResearchQueued),
+    Rejected(ResearchRejected)
+}
+
+pub struct ResearchOption {
+    pub name: String,
+    pub description: String,
+    // Costs after research effects, and as given in the game data
+    pub time_cost: i64,
+    pub inspiration_cost: f64,
+    pub base_time_cost: i64,
+    pub base_inspiration_cost: f64,
+    pub affordable: bool,
+    // Level this research would bring the modifier to
+    pub level: usize,
+    pub max_level: usize
+}
+
+pub struct ResearchStarted {
+    pub name: String,
+    pub end_ts: i64,
+    pub inspiration_spent: f64
+}
+
+pub struct ResearchQueued {
+    pub name: String,
+    // 1-based position in the queue
+    pub position: usize
+}
+
+pub struct ResearchRejected {
+    pub name: String,
+    pub reason: RejectReason
+}
+
+pub enum RejectReason {
+    Locked(LockExplanation),
+    AtMaxLevel { max_level: usize },
+    CannotAfford { cost: f64, missing: f64 },
+    NoFreeSlots
+}
+
+pub struct ResearchCancelled {
+    pub name: String,
+    pub was_queued: bool,
+    pub refund: f64,
+
+    // Queued research that can no longer be unlocked, and was removed too
+    pub dropped: Vec<String>
+}
+
+pub struct ResearchProgress {
+    pub name: String,
+    pub remaining: i64
+}
+
+pub enum TrialStartOutcome {
+    Started { initial_bot_mass: f64 },
+    AlreadyRunning
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrialOutcome {
+    Stopped,
+    Failed,
+    Succeeded,
+    // Ended by the named event
+    Halted(String)
+}
+
+pub struct TrialEnded {
+    pub outcome: TrialOutcome,
+    pub bot_mass: f64,
+    // Bots that survived the self-destruct of a stopped trial
+    pub escaped_bot_mass: f64,
+    pub elapsed: i64,
+
+    // What the public did about any escaped bots, see unease::UneaseBand
+    pub consequences: Vec<EventFired>
+}
+
+pub enum TrialReport {
+    NoTrial,
+    Running { bot_mass: f64, domination: f64, elapsed: i64 },
+    Ended(TrialEnded)
+}
+
+pub struct EventFired {
+    pub name: String,
+    pub description: String,
+    pub ts: i64
+}
+
+pub struct AwaySummary {
+    // Seconds since the last check-in
+    pub away: i64,
+    pub research_completed: Vec<String>,
+    // Names and descriptions of trial events
+    pub events: Vec<(String, String)>,
+    // How many times bigger the trial's bots got, if the same trial was running throughout
+    pub mass_growth: Option<f64>,
+    pub unease_change: f64,
+    pub inspiration_change: f64,
+    // Masses the bots passed, see away::mass_milestones
+    pub milestones: Vec<f64>
+}
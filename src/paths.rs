@@ -0,0 +1,45 @@
+use std::env;
+use std::path::PathBuf;
+
+const APP_NAME: &str = "greygoo";
+const GAME_FILENAME: &str = "game.json";
+
+// Directory holding save profiles (and optionally game data). Resolved in order:
+// $GREYGOO_HOME, $XDG_DATA_HOME/greygoo, ~/.local/share/greygoo, then cwd.
+pub fn data_dir() -> PathBuf {
+    if let Some(home) = non_empty_var("GREYGOO_HOME") {
+        PathBuf::from(home)
+    }
+    else if let Some(xdg_data_home) = non_empty_var("XDG_DATA_HOME") {
+        PathBuf::from(xdg_data_home).join(APP_NAME)
+    }
+    else if let Some(home) = non_empty_var("HOME") {
+        PathBuf::from(home).join(".local").join("share").join(APP_NAME)
+    }
+    else {
+        PathBuf::from(".")
+    }
+}
+
+// Game data is optional on disk: None means use the built-in game data.
+pub fn resolve_game_path(requested: &str) -> Option<PathBuf> {
+    if requested.is_empty() {
+        let path = data_dir().join(GAME_FILENAME);
+        if path.is_file() {
+            Some(path)
+        }
+        else {
+            None
+        }
+    }
+    else {
+        Some(PathBuf::from(requested))
+    }
+}
+
+fn non_empty_var(name: &str) -> Option<String> {
+    match env::var(name) {
+        Ok(value) if !value.is_empty() => Some(value),
+        _ => None
+    }
+}
@@ -0,0 +1,190 @@
+use std::fmt;
+
+use serde::Deserialize;
+
+// One entry of a modifier's `locked_by` list. The list itself is an "all of".
+//
+//   "name"                                   the modifier must be owned
+//   {"any_of": [..]}                          at least one must be met
+//   {"all_of": [..]}                          every one must be met
+//   {"at_least": n, "of": [..]}               n or more must be met
+//   {"not": ..}                               must not be owned, researched or
+//                                             queued; choosing it locks this
+//                                             modifier out for good
+#[derive(Deserialize, Clone)]
+#[serde(untagged)]
+pub enum Prerequisite {
+    Modifier(String),
+    AnyOf { any_of: Vec<Prerequisite> },
+    AllOf { all_of: Vec<Prerequisite> },
+    AtLeast { at_least: usize, of: Vec<Prerequisite> },
+    Not { not: Box<Prerequisite> }
+}
+
+impl Prerequisite {
+    // `owned` answers whether a modifier has been researched; `chosen` whether
+    // it has been researched, or is being researched or queued (used by "not").
+    pub fn is_met<O, C>(&self, owned: &O, chosen: &C) -> bool
+    where O: Fn(&str) -> bool, C: Fn(&str) -> bool
+    {
+        match self {
+            Prerequisite::Modifier(name) => owned(name),
+            Prerequisite::AnyOf { any_of } => any_of.iter().any(|p| p.is_met(owned, chosen)),
+            Prerequisite::AllOf { all_of } => all_of.iter().all(|p| p.is_met(owned, chosen)),
+            Prerequisite::AtLeast { at_least, of } =>
+                of.iter().filter(|p| p.is_met(owned, chosen)).count() >= *at_least,
+            Prerequisite::Not { not } => !not.is_met(chosen, chosen)
+        }
+    }
+
+    // Whether this can never be met again, because of a "not" violated by the
+    // choices made so far (which can't be undone).
+    pub fn is_excluded<C>(&self, chosen: &C) -> bool
+    where C: Fn(&str) -> bool
+    {
+        match self {
+            Prerequisite::Modifier(_) => false,
+            Prerequisite::AnyOf { any_of } => any_of.iter().all(|p| p.is_excluded(chosen)),
+            Prerequisite::AllOf { all_of } => all_of.iter().any(|p| p.is_excluded(chosen)),
+            Prerequisite::AtLeast { at_least, of } =>
+                of.iter().filter(|p| !p.is_excluded(chosen)).count() < *at_least,
+            Prerequisite::Not { not } => not.is_met(chosen, chosen)
+        }
+    }
+
+    // Modifiers holding this up, if only those for which `attainable` is true
+    // can ever be owned: every option of an unmet "any_of", and the unmet
+    // options of an "at_least". A "not" never holds anything up.
+    pub fn blocking_names<A>(&self, attainable: &A) -> Vec<&str>
+    where A: Fn(&str) -> bool
+    {
+        if self.is_met(attainable, &|_: &str| false) {
+            return Vec::new();
+        }
+        match self {
+            Prerequisite::Modifier(name) => vec![name.as_str()],
+            Prerequisite::AnyOf { any_of: list } | Prerequisite::AllOf { all_of: list } | Prerequisite::AtLeast { of: list, .. } =>
+                list.iter().flat_map(|p| p.blocking_names(attainable)).collect(),
+            Prerequisite::Not { .. } => Vec::new()
+        }
+    }
+
+    // Every modifier named, and whether it is named under a "not".
+    pub fn walk<'a, F>(&'a self, negated: bool, f: &mut F)
+    where F: FnMut(&'a str, bool)
+    {
+        match self {
+            Prerequisite::Modifier(name) => f(name, negated),
+            Prerequisite::AnyOf { any_of: list } | Prerequisite::AllOf { all_of: list } | Prerequisite::AtLeast { of: list, .. } => {
+                for p in list {
+                    p.walk(negated, f);
+                }
+            },
+            Prerequisite::Not { not } => not.walk(!negated, f)
+        }
+    }
+}
+
+fn fmt_list(f: &mut fmt::Formatter, list: &[Prerequisite]) -> fmt::Result {
+    write!(f, "(")?;
+    for (i, p) in list.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{}", p)?;
+    }
+    write!(f, ")")
+}
+
+impl fmt::Display for Prerequisite {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Prerequisite::Modifier(name) => write!(f, "{}", name),
+            Prerequisite::AnyOf { any_of } => {
+                write!(f, "any of ")?;
+                fmt_list(f, any_of)
+            },
+            Prerequisite::AllOf { all_of } => {
+                write!(f, "all of ")?;
+                fmt_list(f, all_of)
+            },
+            Prerequisite::AtLeast { at_least, of } => {
+                write!(f, "{} of ", at_least)?;
+                fmt_list(f, of)
+            },
+            Prerequisite::Not { not } => write!(f, "not {}", not)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(json: &str) -> Prerequisite {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn among<'a>(names: &'a [&'a str]) -> impl Fn(&str) -> bool + 'a {
+        move |n: &str| names.contains(&n)
+    }
+
+    #[test]
+    fn is_met() {
+        let owned = among(&["a", "b"]);
+        let chosen = among(&["a", "b", "c"]);
+        let met = |json: &str| parse(json).is_met(&owned, &chosen);
+
+        assert!(met(r#""a""#));
+        assert!(!met(r#""c""#));
+        assert!(met(r#"{"any_of": ["c", "b"]}"#));
+        assert!(!met(r#"{"any_of": ["c", "d"]}"#));
+        assert!(met(r#"{"all_of": ["a", "b"]}"#));
+        assert!(!met(r#"{"all_of": ["a", "c"]}"#));
+        assert!(met(r#"{"at_least": 2, "of": ["a", "b", "c"]}"#));
+        assert!(!met(r#"{"at_least": 2, "of": ["a", "c", "d"]}"#));
+        // "not" goes by what has been chosen, not only what is owned
+        assert!(met(r#"{"not": "d"}"#));
+        assert!(!met(r#"{"not": "c"}"#));
+        assert!(met(r#"{"all_of": ["a", {"not": {"any_of": ["d", "e"]}}]}"#));
+    }
+
+    #[test]
+    fn is_excluded() {
+        let chosen = among(&["a"]);
+        let excluded = |json: &str| parse(json).is_excluded(&chosen);
+
+        assert!(!excluded(r#""b""#));
+        assert!(excluded(r#"{"not": "a"}"#));
+        assert!(!excluded(r#"{"not": "b"}"#));
+        assert!(!excluded(r#"{"any_of": [{"not": "a"}, "b"]}"#));
+        assert!(excluded(r#"{"any_of": [{"not": "a"}, {"not": {"all_of": ["a"]}}]}"#));
+        assert!(excluded(r#"{"all_of": ["b", {"not": "a"}]}"#));
+        assert!(excluded(r#"{"at_least": 2, "of": ["b", {"not": "a"}]}"#));
+        assert!(!excluded(r#"{"at_least": 1, "of": ["b", {"not": "a"}]}"#));
+    }
+
+    #[test]
+    fn blocking_names() {
+        let attainable = among(&["a", "b"]);
+        let blocking = |json: &str| parse(json).blocking_names(&attainable).into_iter().map(String::from).collect::<Vec<_>>();
+
+        assert!(blocking(r#""a""#).is_empty());
+        assert_eq!(blocking(r#""c""#), ["c"]);
+        // One attainable option is a way around the rest
+        assert!(blocking(r#"{"any_of": ["c", "a"]}"#).is_empty());
+        assert_eq!(blocking(r#"{"any_of": ["c", "d"]}"#), ["c", "d"]);
+        assert_eq!(blocking(r#"{"all_of": ["a", "c"]}"#), ["c"]);
+        assert!(blocking(r#"{"at_least": 2, "of": ["a", "b", "c"]}"#).is_empty());
+        assert_eq!(blocking(r#"{"at_least": 2, "of": ["a", "c", "d"]}"#), ["c", "d"]);
+        assert!(blocking(r#"{"not": "c"}"#).is_empty());
+    }
+
+    #[test]
+    fn walk() {
+        let mut names: Vec<(String, bool)> = Vec::new();
+        parse(r#"{"all_of": ["a", {"not": {"any_of": ["b", {"not": "c"}]}}]}"#)
+            .walk(false, &mut |name, negated| names.push((name.to_string(), negated)));
+        assert_eq!(names, [("a".to_string(), false), ("b".to_string(), true), ("c".to_string(), false)]);
+    }
+}
@@ -0,0 +1,105 @@
+use std::fs::{read_dir, remove_file};
+use std::path::PathBuf;
+
+use crate::error::GreyGooError;
+use crate::game::Game;
+use crate::paths::data_dir;
+use crate::read_file_contents::get_contents;
+use crate::state::State;
+use crate::write_file_contents::write_contents;
+
+pub const DEFAULT_PROFILE: &str = "default";
+const PROFILES_DIRNAME: &str = "profiles";
+const ACTIVE_PROFILE_FILENAME: &str = "active_profile";
+
+fn profiles_dir() -> PathBuf {
+    data_dir().join(PROFILES_DIRNAME)
+}
+
+fn check_name(name: &str) -> Result<(), GreyGooError> {
+    let ok = !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if ok {
+        Ok(())
+    }
+    else {
+        Err(GreyGooError::BadProfileName(name.to_string()))
+    }
+}
+
+pub fn profile_save_path(name: &str) -> PathBuf {
+    profiles_dir().join(format!("{}.json", name))
+}
+
+// An explicit save file wins over an explicit profile, which wins over the
+// remembered active profile.
+pub fn resolve_save_path(requested_save: &str, requested_profile: &str) -> Result<PathBuf, GreyGooError> {
+    if !requested_save.is_empty() {
+        Ok(PathBuf::from(requested_save))
+    }
+    else if !requested_profile.is_empty() {
+        check_name(requested_profile)?;
+        Ok(profile_save_path(requested_profile))
+    }
+    else {
+        Ok(profile_save_path(&active_profile()))
+    }
+}
+
+pub fn profile_exists(name: &str) -> bool {
+    profile_save_path(name).is_file()
+}
+
+pub fn active_profile() -> String {
+    match get_contents(data_dir().join(ACTIVE_PROFILE_FILENAME)) {
+        Ok(contents) if !contents.trim().is_empty() => contents.trim().to_string(),
+        _ => DEFAULT_PROFILE.to_string()
+    }
+}
+
+fn set_active_profile(name: &str) -> Result<(), GreyGooError> {
+    write_contents(data_dir().join(ACTIVE_PROFILE_FILENAME), name)
+}
+
+pub fn list_profiles() -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    if let Ok(entries) = read_dir(profiles_dir()) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().map_or(false, |ext| ext == "json") {
+                if let Some(stem) = path.file_stem() {
+                    names.push(stem.to_string_lossy().into_owned());
+                }
+            }
+        }
+    }
+    names.sort();
+    names
+}
+
+pub fn new_profile(name: &str, game: Game) -> Result<(), GreyGooError> {
+    check_name(name)?;
+    if profile_exists(name) {
+        return Err(GreyGooError::ProfileExists(name.to_string()));
+    }
+    State::new(game).save(profile_save_path(name))
+}
+
+pub fn switch_profile(name: &str) -> Result<(), GreyGooError> {
+    check_name(name)?;
+    if !profile_exists(name) {
+        return Err(GreyGooError::NoSuchProfile(name.to_string()));
+    }
+    set_active_profile(name)
+}
+
+pub fn delete_profile(name: &str) -> Result<(), GreyGooError> {
+    check_name(name)?;
+    if !profile_exists(name) {
+        return Err(GreyGooError::NoSuchProfile(name.to_string()));
+    }
+    if active_profile() == name {
+        return Err(GreyGooError::ProfileIsActive(name.to_string()));
+    }
+    let path = profile_save_path(name);
+    remove_file(&path).map_err(|e| GreyGooError::io(path, e))
+}
@@ -1,17 +1,9 @@
-use std::fs::File;
-use std::io::prelude::*;
+use std::fs::read_to_string;
+use std::path::Path;
 
-pub fn get_contents(filename: &str) -> Result<String, String> {
-    if let Ok(mut file) = File::open(filename) {
-        let mut contents = String::new();
-        if let Ok(_) = file.read_to_string(&mut contents) {
-            Ok(contents)
-        }
-        else {
-            Err("failed to read file".to_string())
-        }
-    }
-    else {
-        Err("failed to open file".to_string())
-    }
-}
\ No newline at end of file
+use crate::error::GreyGooError;
+
+pub fn get_contents<P: AsRef<Path>>(filename: P) -> Result<String, GreyGooError> {
+    let filename = filename.as_ref();
+    read_to_string(filename).map_err(|e| GreyGooError::io(filename, e))
+}
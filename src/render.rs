@@ -0,0 +1,391 @@
+use std::collections::HashSet;
+
+use chrono::{Local, TimeZone};
+
+use greygoo::error::GreyGooError;
+use greygoo::fmt_mass::fmt_mass;
+use greygoo::fmt_t::fmt_t;
+use greygoo::log::{LogEntry, LogEvent};
+use greygoo::tree::{NodeStatus, ResearchTree};
+use greygoo::unease::UneaseBand;
+use greygoo::unlock::{LockExplanation, PrerequisiteStatus, UnmetRequirement};
+use greygoo::validate::Issue;
+use greygoo::outcome::{
+    AwaySummary,
+    RejectReason,
+    ResearchCancelled,
+    ResearchOption,
+    ResearchOutcome,
+    ResearchProgress,
+    TrialEnded,
+    TrialOutcome,
+    TrialReport,
+    TrialStartOutcome
+};
+
+pub fn potential_modifiers(options: &[ResearchOption], inspiration: f64) {
+    if options.is_empty() {
+        println!("No potential modifiers.");
+    }
+    else {
+        println!("Potential modifiers ({:.2} inspiration available):", inspiration);
+        for option in options {
+            let unaffordable = if option.affordable { "" } else { ", can't afford" };
+            let level = if option.max_level > 1 { format!(" level {}/{}", option.level, option.max_level) } else { String::new() };
+            let base_time = if option.time_cost != option.base_time_cost { format!(" (was {})", fmt_t(option.base_time_cost)) } else { String::new() };
+            let base_inspiration = if (option.inspiration_cost - option.base_inspiration_cost).abs() > 1e-9 {
+                format!(" (was {:.2})", option.base_inspiration_cost)
+            }
+            else {
+                String::new()
+            };
+            println!(" - {}{} ({}{}, {:.2} inspiration{}{}): {}",
+                     option.name, level, fmt_t(option.time_cost), base_time, option.inspiration_cost, base_inspiration, unaffordable, option.description);
+        }
+    }
+}
+
+pub fn research_outcome(outcome: &ResearchOutcome) {
+    match outcome {
+        ResearchOutcome::Started(started) => {
+            if started.inspiration_spent > 0f64 {
+                println!("Starting research into \"{}\", spending {:.2} inspiration", started.name, started.inspiration_spent);
+            }
+            else {
+                println!("Starting research into \"{}\"", started.name);
+            }
+        },
+        ResearchOutcome::Queued(queued) => {
+            println!("Queued research into \"{}\" (position {} in queue)", queued.name, queued.position);
+        },
+        ResearchOutcome::Rejected(rejected) => match &rejected.reason {
+            RejectReason::Locked(explanation) => {
+                println!("Cannot research \"{}\", as it is locked", rejected.name);
+                lock_explanation(explanation);
+            },
+            RejectReason::AtMaxLevel { max_level } => {
+                if *max_level == 1 {
+                    println!("Cannot research \"{}\", it is already researched, in progress or queued", rejected.name);
+                }
+                else {
+                    println!("Cannot research \"{}\", all {} levels are already researched, in progress or queued", rejected.name, max_level);
+                }
+            },
+            RejectReason::CannotAfford { cost, missing } => {
+                println!("Cannot research \"{}\", it costs {:.2} inspiration and you are {:.2} short", rejected.name, cost, missing);
+            },
+            RejectReason::NoFreeSlots => {
+                println!("Cannot research \"{}\", all lab slots are busy. Use --queue-research to queue it.", rejected.name);
+            }
+        }
+    }
+}
+
+fn lock_explanation(explanation: &LockExplanation) {
+    if !explanation.unmet.is_empty() {
+        println!("Missing:");
+        for requirement in &explanation.unmet {
+            match requirement {
+                UnmetRequirement::Prerequisite { name, status } => {
+                    let status = match status {
+                        PrerequisiteStatus::Researching { remaining } => format!("researching, {} to go", fmt_t(*remaining)),
+                        PrerequisiteStatus::Queued => "queued".to_string(),
+                        PrerequisiteStatus::NotStarted => "not started".to_string()
+                    };
+                    println!(" - {} ({})", name, status);
+                },
+                UnmetRequirement::Condition(prerequisite) => {
+                    println!(" - {}", prerequisite);
+                },
+                UnmetRequirement::LockedOut => {
+                    println!(" - locked out by a rival branch you chose");
+                }
+            }
+        }
+    }
+
+    if !explanation.reachable {
+        println!("It can no longer be unlocked in this game.");
+    }
+    else if !explanation.path.is_empty() {
+        println!("Unlocks after researching {} ({}, {:.2} inspiration).",
+                 explanation.path.join(" -> "), fmt_t(explanation.total_time), explanation.total_inspiration);
+    }
+}
+
+pub fn research_cancelled(mod_name: &str, cancelled: &Option<ResearchCancelled>) {
+    match cancelled {
+        Some(cancelled) if cancelled.was_queued => {
+            println!("Removed \"{}\" from the research queue.", cancelled.name);
+        },
+        Some(cancelled) => {
+            println!("Cancelled research into \"{}\", {:.2} inspiration refunded.", cancelled.name, cancelled.refund);
+        },
+        None => {
+            println!("No research into \"{}\" to cancel.", mod_name);
+        }
+    }
+    if let Some(cancelled) = cancelled {
+        for name in &cancelled.dropped {
+            println!("Removed \"{}\" from the research queue, as it can no longer be unlocked.", name);
+        }
+    }
+}
+
+pub fn away_summary(summary: &AwaySummary) {
+    let nothing_happened = summary.research_completed.is_empty() && summary.events.is_empty()
+        && summary.milestones.is_empty() && summary.mass_growth.is_none();
+    // Not worth a summary for a quick look back
+    if summary.away < 60 || nothing_happened {
+        return;
+    }
+
+    println!("While you were away ({}):", fmt_t(summary.away));
+    for name in &summary.research_completed {
+        println!(" - Research into \"{}\" completed", name);
+    }
+    for (name, description) in &summary.events {
+        println!(" - {}: {}", name, description);
+    }
+    if let Some(growth) = summary.mass_growth {
+        if growth < 1000f64 {
+            println!(" - Bot mass grew {:.2}x", growth);
+        }
+        else {
+            println!(" - Bot mass grew {:.2e}x", growth);
+        }
+    }
+    for milestone in &summary.milestones {
+        println!(" - Bots passed {}", fmt_mass(*milestone));
+    }
+    if summary.unease_change.abs() >= 0.005 {
+        println!(" - Population unease {:+.2}", summary.unease_change);
+    }
+    if summary.inspiration_change.abs() >= 0.005 {
+        println!(" - Scientific inspiration {:+.2}", summary.inspiration_change);
+    }
+}
+
+pub fn research_progress(progress: &[ResearchProgress], queue: &[String], inspiration: f64) {
+    println!("Scientific inspiration: {:.2}", inspiration);
+    if progress.is_empty() {
+        println!("No research in progress.");
+    }
+    else {
+        println!("Research in progress:");
+        for p in progress {
+            if p.remaining < 0 {
+                println!(" - {} (overdue by {})", p.name, fmt_t(-p.remaining));
+            }
+            else {
+                println!(" - {} ({} to go)", p.name, fmt_t(p.remaining));
+            }
+        }
+    }
+
+    if !queue.is_empty() {
+        println!("Research queued:");
+        for (i, mod_name) in queue.iter().enumerate() {
+            println!(" {}. {}", i + 1, mod_name);
+        }
+    }
+}
+
+pub fn population_unease(unease: f64, band: Option<&UneaseBand>) {
+    match band {
+        Some(band) => println!("Population unease {:.1} // {}", unease, band.name),
+        None => println!("Population unease {:.1}", unease)
+    }
+}
+
+pub fn trial_start(outcome: &TrialStartOutcome) {
+    match outcome {
+        TrialStartOutcome::Started { .. } => println!("New trial begun!"),
+        TrialStartOutcome::AlreadyRunning => println!("Cannot start a new trial while another is in progress.")
+    }
+}
+
+pub fn trial_ended(ended: &TrialEnded) {
+    match &ended.outcome {
+        TrialOutcome::Succeeded => println!("Trial success! You win!"),
+        TrialOutcome::Halted(event_name) => {
+            println!("Trial halted by {}. {} of bots were lost. {} of research time, wasted.",
+                     event_name, fmt_mass(ended.bot_mass), fmt_t(ended.elapsed));
+        },
+        TrialOutcome::Stopped | TrialOutcome::Failed => {
+            let stopped = if matches!(ended.outcome, TrialOutcome::Failed) { "failed" } else { "stopped" };
+            println!("Trial {}. {} of bots were silenced. {} of research time, wasted.",
+                     stopped, fmt_mass(ended.bot_mass - ended.escaped_bot_mass), fmt_t(ended.elapsed));
+            if ended.escaped_bot_mass > 0f64 {
+                println!("{} of bots escaped the self-destruct. People have noticed.", fmt_mass(ended.escaped_bot_mass));
+            }
+        }
+    }
+    for consequence in &ended.consequences {
+        println!("{}: {}", consequence.name, consequence.description);
+    }
+}
+
+pub fn trial_report(report: &TrialReport) {
+    match report {
+        TrialReport::NoTrial => println!("No trial in progress."),
+        TrialReport::Running { bot_mass, domination, elapsed } => {
+            println!("Trial running // Bots totalling {} (~{:.1}% domination) // {} elapsed",
+                     fmt_mass(*bot_mass), 100f64 * domination, fmt_t(*elapsed));
+        },
+        TrialReport::Ended(ended) => trial_ended(ended)
+    }
+}
+
+fn fmt_timestamp(ts: i64) -> String {
+    match Local.timestamp_opt(ts, 0).single() {
+        Some(dt) => dt.format("%Y-%m-%d %H:%M:%S").to_string(),
+        None => ts.to_string()
+    }
+}
+
+fn trial_outcome_verb(outcome: &TrialOutcome) -> String {
+    match outcome {
+        TrialOutcome::Stopped => "stopped".to_string(),
+        TrialOutcome::Failed => "failed".to_string(),
+        TrialOutcome::Succeeded => "succeeded".to_string(),
+        TrialOutcome::Halted(event_name) => format!("halted by {}", event_name)
+    }
+}
+
+pub fn log_entries(entries: &[&LogEntry]) {
+    if entries.is_empty() {
+        println!("Nothing in the log.");
+    }
+    for entry in entries {
+        let message = match &entry.event {
+            LogEvent::ResearchStarted { name, inspiration_spent } =>
+                format!("Started research into \"{}\" ({:.2} inspiration)", name, inspiration_spent),
+            LogEvent::ResearchCompleted { name } =>
+                format!("Completed research into \"{}\"", name),
+            LogEvent::ResearchCancelled { name, was_queued: true, .. } =>
+                format!("Removed \"{}\" from the research queue", name),
+            LogEvent::ResearchCancelled { name, refund, .. } =>
+                format!("Cancelled research into \"{}\" ({:.2} inspiration refunded)", name, refund),
+            LogEvent::TrialStarted { initial_bot_mass } =>
+                format!("Trial started with {} of bots", fmt_mass(*initial_bot_mass)),
+            LogEvent::TrialEnded { outcome, bot_mass, escaped_bot_mass } => {
+                let escaped = if *escaped_bot_mass > 0f64 { format!(", {} escaped", fmt_mass(*escaped_bot_mass)) } else { String::new() };
+                format!("Trial {} at {} of bots{}", trial_outcome_verb(outcome), fmt_mass(*bot_mass), escaped)
+            },
+            LogEvent::TrialEvent { name, description }
+            | LogEvent::UneaseConsequence { name, description } =>
+                format!("{}: {}", name, description)
+        };
+        println!("{}  {:<8}  {}", fmt_timestamp(entry.ts), entry.event.kind().name(), message);
+    }
+}
+
+pub fn validation_issues(issues: &[Issue]) {
+    if issues.is_empty() {
+        println!("Game data OK.");
+    }
+    for issue in issues {
+        println!("{}: {}", issue.path, issue.message);
+    }
+}
+
+fn status_marker(status: &NodeStatus) -> &'static str {
+    match status {
+        NodeStatus::Owned => "[x]",
+        NodeStatus::InProgress => "[~]",
+        NodeStatus::Queued => "[q]",
+        NodeStatus::Available => "[ ]",
+        NodeStatus::Locked => "[-]",
+        NodeStatus::LockedOut => "[!]"
+    }
+}
+
+pub fn research_tree_ascii(tree: &ResearchTree) {
+    println!("Research tree ([x] owned, [~] in progress, [q] queued, [ ] available, [-] locked, [!] locked out):");
+    let mut expanded: HashSet<&str> = HashSet::new();
+    for root in tree.roots() {
+        tree_branch(tree, root, "", "", &mut expanded);
+    }
+}
+
+fn tree_branch<'a>(tree: &'a ResearchTree, name: &'a str, first_prefix: &str, prefix: &str, expanded: &mut HashSet<&'a str>) {
+    let node = match tree.node(name) {
+        Some(node) => node,
+        None => return
+    };
+
+    let excludes: Vec<&str> = tree.edges.iter()
+        .filter(|e| e.to == name && e.negated)
+        .map(|e| e.from.as_str())
+        .collect();
+    let excludes = if excludes.is_empty() { String::new() } else { format!(", not {}", excludes.join(", ")) };
+
+    let children = tree.children(name);
+    // Modifiers with several prerequisites appear under each; only expand them once.
+    let seen = !expanded.insert(name);
+    let see_above = if seen && !children.is_empty() { " (see above)" } else { "" };
+    println!("{}{} {} ({}{}){}", first_prefix, status_marker(&node.status), name, fmt_t(node.time_cost), excludes, see_above);
+    if seen {
+        return;
+    }
+
+    for (i, edge) in children.iter().enumerate() {
+        let last = i + 1 == children.len();
+        let (branch, continuation) = if last { ("└── ", "    ") } else { ("├── ", "│   ") };
+        tree_branch(tree, &edge.to,
+                    &format!("{}{}", prefix, branch),
+                    &format!("{}{}", prefix, continuation),
+                    expanded);
+    }
+}
+
+fn dot_id(name: &str) -> String {
+    format!("\"{}\"", dot_escape(name))
+}
+
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+pub fn research_tree_dot(tree: &ResearchTree) {
+    println!("digraph research {{");
+    println!("    rankdir=LR;");
+    println!("    node [shape=box, style=filled];");
+    for node in &tree.nodes {
+        let (colour, status) = match node.status {
+            NodeStatus::Owned => ("palegreen", "owned"),
+            NodeStatus::InProgress => ("gold", "in progress"),
+            NodeStatus::Queued => ("lightyellow", "queued"),
+            NodeStatus::Available => ("lightblue", "available"),
+            NodeStatus::Locked => ("lightgrey", "locked"),
+            NodeStatus::LockedOut => ("grey50", "locked out")
+        };
+        println!("    {} [label=\"{}\\n{}\", fillcolor={}];",
+                 dot_id(&node.name), dot_escape(&node.name), status, colour);
+    }
+    for edge in &tree.edges {
+        if edge.negated {
+            println!("    {} -> {} [label=\"not\", style=dashed, color=red];", dot_id(&edge.from), dot_id(&edge.to));
+        }
+        else {
+            println!("    {} -> {} [label={}];", dot_id(&edge.from), dot_id(&edge.to), dot_id(&fmt_t(edge.time_cost)));
+        }
+    }
+    println!("}}");
+}
+
+// With a pointer to the option that would help, where there is one.
+pub fn error(e: &GreyGooError) {
+    let hint = match e {
+        GreyGooError::SaveExists(_) => Some("use --force to overwrite it"),
+        GreyGooError::NoSave(_) => Some("start one with --new-game"),
+        GreyGooError::UnknownModifier(_) => Some("use --list-research to see what is available"),
+        GreyGooError::NoSuchProfile(_) => Some("use \"profile list\" to see existing profiles"),
+        _ => None
+    };
+    match hint {
+        Some(hint) => eprintln!("Error: {}; {}", e, hint),
+        None => eprintln!("Error: {}", e)
+    }
+}
@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+#[serde(from="ResearchRecord")]
+pub struct Research {
+    pub name: String,
+
+    // Timings
+    pub start_ts: i64,
+    pub end_ts: i64,
+
+    // Costs
+    pub inspiration_spent: f64
+}
+
+// What research into a modifier costs, after research speed and any
+// research effects of owned modifiers.
+pub struct ResearchCost {
+    pub time: i64,
+    pub inspiration: f64
+}
+
+impl Research {
+    // Fraction of the research time still to go, at `now`.
+    pub fn fraction_remaining(&self, now: i64) -> f64 {
+        let total = self.end_ts - self.start_ts;
+        if total <= 0 {
+            0f64
+        }
+        else {
+            ((self.end_ts - now) as f64 / total as f64).clamp(0f64, 1f64)
+        }
+    }
+}
+
+// Older saves stored research in progress as [name, end_ts].
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ResearchRecord {
+    Full { name: String, start_ts: i64, end_ts: i64, inspiration_spent: f64 },
+    Legacy(String, i64)
+}
+
+impl From<ResearchRecord> for Research {
+    fn from(record: ResearchRecord) -> Research {
+        match record {
+            ResearchRecord::Full { name, start_ts, end_ts, inspiration_spent } =>
+                Research { name, start_ts, end_ts, inspiration_spent },
+            ResearchRecord::Legacy(name, end_ts) =>
+                Research { name, start_ts: end_ts, end_ts, inspiration_spent: 0f64 }
+        }
+    }
+}
@@ -0,0 +1,24 @@
+// SplitMix64. Small, fast and good enough for sampling event times; the
+// whole state is one u64 so it can live in the save.
+pub struct Rng {
+    pub state: u64
+}
+
+impl Rng {
+    pub fn new(state: u64) -> Rng {
+        Rng { state }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    // Uniform in (0, 1], so it is safe to take the log of.
+    pub fn next_f64(&mut self) -> f64 {
+        ((self.next_u64() >> 11) + 1) as f64 / (1u64 << 53) as f64
+    }
+}
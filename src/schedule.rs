@@ -0,0 +1,277 @@
+// Catching up on the time since the game was last looked at.
+//
+// Everything that changes the stats (research completing, trial events, the
+// trial reaching world domination, a condition's threshold being crossed)
+// is handled in the order it happened, and the trial is advanced between
+// them using the stats in force at the time. Between those moments the
+// stats are constant, so bot mass grows exponentially and unease and
+// inspiration linearly, and the moment a threshold is crossed can be solved
+// for exactly. The result doesn't depend on how often the game is checked.
+
+use crate::condition::{Condition, Variable};
+use crate::error::GreyGooError;
+use crate::log::LogEvent;
+use crate::outcome::EventFired;
+use crate::state::State;
+use crate::trial::PendingEvent;
+
+enum Happening {
+    // Index into modifiers_in_progress
+    ResearchComplete(usize),
+    TrialEvent(PendingEvent),
+    ThresholdCrossed,
+    WorldDominated
+}
+
+impl Happening {
+    // Order of things happening at the same moment: finished research
+    // changes the stats before anything else is looked at.
+    fn rank(&self) -> u8 {
+        match self {
+            Happening::ResearchComplete(_) => 0,
+            Happening::TrialEvent(_) => 1,
+            Happening::ThresholdCrossed => 2,
+            Happening::WorldDominated => 3
+        }
+    }
+}
+
+impl State {
+    // Brings the game up to the current time.
+    pub fn update(&mut self) -> Result<Vec<EventFired>, GreyGooError> {
+        let now = self.clock.now();
+        self.advance_to(now)
+    }
+
+    pub(crate) fn advance_to(&mut self, until_ts: i64) -> Result<Vec<EventFired>, GreyGooError> {
+        let mut events: Vec<EventFired> = Vec::new();
+
+        loop {
+            if self.is_trial_live() && self.trial_in_progress.as_ref().map_or(false, |t| t.next_event.is_none()) {
+                let next_event = self.get_next_event()?;
+                if let Some(trial) = self.trial_in_progress.as_mut() {
+                    trial.next_event = next_event;
+                }
+            }
+
+            let (ts, happening) = match self.next_happening(until_ts)? {
+                Some(next) => next,
+                None => break
+            };
+
+            self.advance_world_to(ts)?;
+            match happening {
+                Happening::ResearchComplete(i) => {
+                    let research = self.modifiers_in_progress.remove(i);
+                    self.record(research.end_ts, LogEvent::ResearchCompleted { name: research.name.clone() });
+                    self.active_modifiers.push(research.name);
+                    self.start_queued_research(research.end_ts)?;
+                    // The stats have changed, and with them which events can
+                    // happen, so the next one is drawn again.
+                    self.clear_next_event();
+                },
+                Happening::TrialEvent(pending) => {
+                    self.clear_next_event();
+                    if let Some(fired) = self.run_event(&pending)? {
+                        self.record(fired.ts, LogEvent::TrialEvent { name: fired.name.clone(), description: fired.description.clone() });
+                        events.push(fired);
+                    }
+                },
+                Happening::ThresholdCrossed => {
+                    // Effects and events may have switched on or off, and
+                    // queued research may have become affordable.
+                    self.start_queued_research(ts)?;
+                    self.clear_next_event();
+                },
+                Happening::WorldDominated => {
+                    let world_mass = self.game.world_mass;
+                    if let Some(trial) = self.trial_in_progress.as_mut() {
+                        trial.bot_mass = trial.bot_mass.max(world_mass);
+                    }
+                }
+            }
+            self.check_unease_bands_at(ts, &mut events)?;
+        }
+
+        self.advance_world_to(until_ts)?;
+        self.check_unease_bands_at(until_ts, &mut events)?;
+        self.start_queued_research(until_ts)?;
+        Ok(events)
+    }
+
+    fn check_unease_bands_at(&mut self, ts: i64, events: &mut Vec<EventFired>) -> Result<(), GreyGooError> {
+        let fired = self.check_unease_bands(ts)?;
+        if !fired.is_empty() {
+            // The trial may have been cut back or ended
+            self.clear_next_event();
+            events.extend(fired);
+        }
+        Ok(())
+    }
+
+    // The earliest thing to happen no later than until_ts.
+    fn next_happening(&self, until_ts: i64) -> Result<Option<(i64, Happening)>, GreyGooError> {
+        let mut candidates: Vec<(i64, Happening)> = Vec::new();
+
+        let next_research = self.modifiers_in_progress.iter()
+            .enumerate()
+            .min_by_key(|(_, r)| r.end_ts);
+        if let Some((i, research)) = next_research {
+            candidates.push((research.end_ts, Happening::ResearchComplete(i)));
+        }
+
+        if self.is_trial_live() {
+            if let Some(pending) = self.trial_in_progress.as_ref().and_then(|t| t.next_event.clone()) {
+                candidates.push((pending.ts, Happening::TrialEvent(pending)));
+            }
+            if let Some(ts) = self.domination_ts()? {
+                candidates.push((ts, Happening::WorldDominated));
+            }
+        }
+        if let Some(ts) = self.next_threshold_crossing()? {
+            candidates.push((ts, Happening::ThresholdCrossed));
+        }
+
+        Ok(candidates.into_iter()
+            .filter(|(ts, _)| *ts <= until_ts)
+            .min_by_key(|(ts, happening)| (*ts, happening.rank())))
+    }
+
+    // Running, and neither won, lost nor halted.
+    fn is_trial_live(&self) -> bool {
+        self.trial_in_progress.as_ref().map_or(false, |t| {
+            t.halted_by.is_none() && t.bot_mass > 0f64 && t.bot_mass < self.game.world_mass
+        })
+    }
+
+    // A live trial grows; otherwise unease dies down.
+    fn advance_world_to(&mut self, ts: i64) -> Result<(), GreyGooError> {
+        if self.is_trial_live() {
+            self.advance_trial_to(ts)?;
+        }
+        else if let Some(last_ts) = self.last_update_ts.filter(|last_ts| *last_ts < ts) {
+            let decay = self.get_stats()?.unease_decay;
+            let decayed = decay * (ts - last_ts) as f64 / self.game.tau;
+            self.population_unease = (self.population_unease - decayed).max(0f64);
+        }
+        self.last_update_ts = Some(self.last_update_ts.map_or(ts, |last_ts| last_ts.max(ts)));
+        Ok(())
+    }
+
+    fn advance_trial_to(&mut self, ts: i64) -> Result<(), GreyGooError> {
+        let behind = self.trial_in_progress.as_ref().map_or(false, |t| t.last_update_ts < ts);
+        if self.is_trial_live() && behind {
+            self.update_trial_until(ts)?;
+        }
+        Ok(())
+    }
+
+    fn clear_next_event(&mut self) {
+        if let Some(trial) = self.trial_in_progress.as_mut() {
+            trial.next_event = None;
+        }
+    }
+
+    // When the bots will reach the world's mass at the current growth rate,
+    // rounded up to the next whole second.
+    fn domination_ts(&self) -> Result<Option<i64>, GreyGooError> {
+        let trial = match &self.trial_in_progress {
+            Some(trial) => trial,
+            None => return Ok(None)
+        };
+        let stats = self.get_stats()?;
+        let factor = 1f64 + stats.growth_rate - stats.death_rate;
+        if factor <= 1f64 {
+            return Ok(None);
+        }
+        let dt = self.game.tau * (self.game.world_mass / trial.bot_mass).ln() / factor.ln();
+        Ok(Some(trial.last_update_ts + dt.ceil().max(0f64) as i64))
+    }
+
+    // Thresholds that matter: those in the conditions of owned modifiers'
+    // effects and of events, the cost of queued research, and the unease bands.
+    fn watched_thresholds(&self) -> Result<Vec<(Variable, f64)>, GreyGooError> {
+        let mut conditions: Vec<&Condition> = Vec::new();
+        for (mod_name, modifier) in &self.game.modifiers {
+            if self.is_owned(mod_name) {
+                conditions.extend(modifier.effects.values().filter_map(|e| e.condition.get()));
+            }
+        }
+        conditions.extend(self.game.events.values().filter_map(|e| e.condition.get()));
+
+        let mut thresholds: Vec<(Variable, f64)> = conditions.iter().flat_map(|c| c.thresholds()).collect();
+        for mod_name in &self.research_queue {
+            if let Some(modifier) = self.game.modifiers.get(mod_name) {
+                thresholds.push((Variable::ScientificInspiration, self.research_cost(modifier)?.inspiration));
+            }
+        }
+        thresholds.extend(self.game.unease_bands.iter().map(|b| (Variable::PopulationUnease, b.threshold)));
+        Ok(thresholds)
+    }
+
+    // The first whole second at or after a watched threshold is crossed.
+    // Without a live trial only unease changes, as it decays.
+    fn next_threshold_crossing(&self) -> Result<Option<i64>, GreyGooError> {
+        let trial = self.trial_in_progress.as_ref().filter(|_| self.is_trial_live());
+        let last_ts = match (trial, self.last_update_ts) {
+            (Some(trial), _) => trial.last_update_ts,
+            (None, Some(last_ts)) => last_ts,
+            (None, None) => return Ok(None)
+        };
+        let stats = self.get_stats()?;
+        let tau = self.game.tau;
+
+        let mut earliest: Option<f64> = None;
+        for (variable, threshold) in self.watched_thresholds()? {
+            // Seconds from the last update until the variable reaches the threshold
+            let dt = match variable {
+                Variable::BotMass | Variable::Domination => {
+                    let trial = match trial {
+                        Some(trial) => trial,
+                        None => continue
+                    };
+                    let threshold = if variable == Variable::Domination { threshold * self.game.world_mass } else { threshold };
+                    let factor = 1f64 + stats.growth_rate - stats.death_rate;
+                    if factor <= 0f64 || factor == 1f64 || threshold <= 0f64 {
+                        continue;
+                    }
+                    tau * (threshold / trial.bot_mass).ln() / factor.ln()
+                },
+                Variable::TrialElapsed => match trial {
+                    Some(trial) => threshold - (trial.last_update_ts - trial.start_ts) as f64,
+                    None => continue
+                },
+                Variable::PopulationUnease => {
+                    let rate = if trial.is_some() { stats.unease_gain } else { -stats.unease_decay };
+                    let stuck = (rate > 0f64 && self.population_unease >= 100f64)
+                        || (rate < 0f64 && self.population_unease <= 0f64);
+                    if stuck || !(0f64..=100f64).contains(&threshold) || rate == 0f64 {
+                        continue;
+                    }
+                    tau * (threshold - self.population_unease) / rate
+                },
+                Variable::ScientificInspiration => {
+                    if trial.is_none() {
+                        continue;
+                    }
+                    let capped = self.scientific_inspiration >= stats.inspiration_cap;
+                    if capped || threshold > stats.inspiration_cap || stats.inspiration_gain == 0f64 {
+                        continue;
+                    }
+                    tau * (threshold - self.scientific_inspiration) / stats.inspiration_gain
+                },
+                // Only change at the moments the scheduler already stops at
+                Variable::ModifiersOwned | Variable::ResearchInProgress | Variable::ResearchQueued => continue
+            };
+
+            if dt.is_finite() && dt >= 0f64 && earliest.map_or(true, |e| dt < e) {
+                earliest = Some(dt);
+            }
+        }
+
+        // Rounded up to a whole second, allowing for rounding errors in the
+        // running totals, and at least one second on so that a variable
+        // sitting exactly on a threshold still moves past it.
+        Ok(earliest.map(|dt| last_ts + (dt - 1e-6).ceil().max(1f64) as i64))
+    }
+}
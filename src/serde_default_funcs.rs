@@ -1,2 +1,6 @@
 pub fn one() -> f64 { 1f64 }
-pub fn zero() -> f64 { 0f64 }
\ No newline at end of file
+pub fn zero() -> f64 { 0f64 }
+pub fn half() -> f64 { 0.5f64 }
+pub fn ten() -> f64 { 10f64 }
+pub fn one_slot() -> usize { 1 }
+pub fn one_level() -> usize { 1 }
\ No newline at end of file
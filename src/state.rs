@@ -1,24 +1,56 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
+use std::path::Path;
+use std::rc::Rc;
 
-use chrono::Utc;
 use serde::{Serialize, Deserialize};
 
+use crate::away::Snapshot;
+use crate::clock::{Clock, system_clock};
+use crate::effect::{apply_effects, Effect, Stat};
+use crate::error::GreyGooError;
+use crate::event::EventOutcome;
 use crate::modifier::Modifier;
+use crate::research::{Research, ResearchCost};
+use crate::rng::Rng;
 use crate::trial::{
+    PendingEvent,
     Trial,
     TrialStatus
 };
 use crate::game::Game;
+use crate::log::{LogEntry, LogEvent};
 use crate::read_file_contents::get_contents;
 use crate::write_file_contents::write_contents;
-use crate::fmt_t::fmt_t;
-use crate::fmt_mass::fmt_mass;
+use crate::outcome::{
+    EventFired,
+    RejectReason,
+    ResearchCancelled,
+    ResearchOption,
+    ResearchOutcome,
+    ResearchProgress,
+    ResearchQueued,
+    ResearchRejected,
+    ResearchStarted,
+    TrialEnded,
+    TrialOutcome,
+    TrialReport,
+    TrialStartOutcome
+};
 use crate::serde_default_funcs::zero;
 
 #[derive(Serialize, Deserialize)]
 pub struct State {
     pub active_modifiers: Vec<String>,
-    pub modifiers_in_progress: Vec<(String, i64)>,
+    pub modifiers_in_progress: Vec<Research>,
+
+    // Research waiting for a free lab slot, in order
+    #[serde(default)]
+    pub research_queue: Vec<String>,
+
+    // Modifiers made unavailable by choosing a rival branch
+    #[serde(default)]
+    pub locked_out: Vec<String>,
+
     pub trial_in_progress: Option<Trial>,
     
     // Permanent stats
@@ -28,76 +60,175 @@ pub struct State {
     #[serde(default="zero")]
     pub scientific_inspiration: f64,
 
+    // How many of the game's unease bands have had their consequences,
+    // lowest first
+    #[serde(default)]
+    pub unease_bands_fired: usize,
+
+    // When the game was last brought up to date
+    #[serde(default)]
+    pub last_update_ts: Option<i64>,
+
+    // Most recent last, see log::LOG_CAPACITY
+    #[serde(default)]
+    pub log: Vec<LogEntry>,
+
+    // How the game looked at the player's last check-in
+    #[serde(default)]
+    pub(crate) last_seen: Option<Snapshot>,
+
     #[serde(skip)]
-    pub game: Game
+    pub game: Game,
+
+    #[serde(skip, default="system_clock")]
+    pub clock: Rc<dyn Clock>
 }
 
+// Everything modifiers can affect. Each starts from a base value, which the
+// effects of every owned modifier then adjust (see effect::apply_effects).
 #[derive(Debug)]
 pub struct Stats {
     pub initial_bot_mass: f64,
     pub growth_rate: f64,
     pub death_rate: f64,
     pub unease_gain: f64,
-    pub inspiration_gain: f64
+    // Unease lost per tau while no trial is running
+    pub unease_decay: f64,
+    pub inspiration_gain: f64,
+    pub inspiration_cap: f64,
+    // Research time is divided by this
+    pub research_speed: f64,
+    // Inspiration cost of research is multiplied by this
+    pub research_cost: f64,
+    pub research_slots: usize,
+    // Fraction of bots destroyed when a trial is stopped
+    pub self_destruct_reliability: f64
 }
 
+// Inspiration and unease build up in many small steps, so allow for rounding
+// errors when comparing them against a threshold.
+pub(crate) fn reaches(value: f64, threshold: f64) -> bool {
+    value + 1e-9 >= threshold
+}
 
 impl State {
 
-    pub fn load(filename: &str) -> State {
-        let contents = get_contents(filename).unwrap();
-        let mut w: State = serde_json::from_str(&contents).unwrap();
-        w.update_modifiers_in_progress();
-        w.update_trial_in_progress();
-        w
+    pub fn new(game: Game) -> State {
+        State::new_with_clock(game, system_clock())
     }
 
-    pub fn save(&self, filename: &str) {
-        let fc = serde_json::to_string(self).expect("could not serialise");
-        write_contents(filename, fc.as_str()).unwrap();
+    pub fn new_with_clock(game: Game, clock: Rc<dyn Clock>) -> State {
+        let mut w = State {
+            active_modifiers: Vec::new(),
+            modifiers_in_progress: Vec::new(),
+            research_queue: Vec::new(),
+            locked_out: Vec::new(),
+            trial_in_progress: None,
+            population_unease: 0f64,
+            scientific_inspiration: 0f64,
+            unease_bands_fired: 0,
+            log: Vec::new(),
+            last_update_ts: Some(clock.now()),
+            last_seen: None,
+            game,
+            clock
+        };
+        w.mark_seen();
+        w
     }
 
-    fn update_modifiers_in_progress(&mut self) {
-        let now = Utc::now().naive_utc().timestamp();
-        let mut newly_complete_modifiers: HashSet<String> = HashSet::new();
-        for (mod_name, ts_complete) in &self.modifiers_in_progress {
-            if *ts_complete <= now {
-                newly_complete_modifiers.insert(mod_name.to_string());
-                self.active_modifiers.push(mod_name.to_string());
+    pub fn new_game(game: Game, difficulty: &str) -> Result<State, GreyGooError> {
+        let mut w = State::new(game);
+        if difficulty.is_empty() {
+            return Ok(w);
+        }
+
+        match w.game.difficulties.get(difficulty) {
+            Some(d) => {
+                for mod_name in &d.starting_modifiers {
+                    if !w.game.modifiers.contains_key(mod_name) {
+                        return Err(GreyGooError::UnknownModifier(mod_name.to_string()));
+                    }
+                }
+                w.active_modifiers = d.starting_modifiers.clone();
+                w.population_unease = d.population_unease;
+                w.scientific_inspiration = d.scientific_inspiration;
+                w.lock_out_rivals();
+                w.mark_seen();
+                Ok(w)
+            },
+            None => {
+                let mut available: Vec<String> = w.game.difficulties.keys().cloned().collect();
+                available.sort();
+                Err(GreyGooError::UnknownDifficulty { name: difficulty.to_string(), available })
             }
         }
+    }
 
-        self.modifiers_in_progress.retain(|(n, _tsc)| !newly_complete_modifiers.contains(n));
+    pub fn load<P: AsRef<Path>>(filename: P, game: Game) -> Result<State, GreyGooError> {
+        State::load_with_clock(filename, game, system_clock())
     }
 
-    fn update_trial_in_progress(&mut self) {
-        if self.trial_in_progress.is_some() {
-            self.update_trial();
+    pub fn load_with_clock<P: AsRef<Path>>(filename: P, game: Game, clock: Rc<dyn Clock>) -> Result<State, GreyGooError> {
+        let filename = filename.as_ref();
+        if !filename.exists() {
+            return Err(GreyGooError::NoSave(filename.to_path_buf()));
+        }
+        let contents = get_contents(filename)?;
+        let mut w: State = serde_json::from_str(&contents)
+            .map_err(|e| GreyGooError::json(filename, e))?;
+        w.game = game;
+        w.clock = clock;
+        w.update()?;
+        Ok(w)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, filename: P) -> Result<(), GreyGooError> {
+        let filename = filename.as_ref();
+        let fc = serde_json::to_string(self).map_err(|e| GreyGooError::json(filename, e))?;
+        write_contents(filename, fc.as_str())
+    }
+
+    fn free_research_slots(&self) -> Result<usize, GreyGooError> {
+        let stats = self.get_stats()?;
+        Ok(stats.research_slots.saturating_sub(self.modifiers_in_progress.len()))
+    }
+
+    // Fill free slots with the first queued research whose prerequisites are met.
+    pub(crate) fn start_queued_research(&mut self, start_ts: i64) -> Result<(), GreyGooError> {
+        while self.free_research_slots()? > 0 {
+            let mut next: Option<usize> = None;
+            for (i, mod_name) in self.research_queue.iter().enumerate() {
+                if let Some(modifier) = self.game.modifiers.get(mod_name) {
+                    if self.is_unlocked(modifier) && self.can_afford(modifier)? {
+                        next = Some(i);
+                        break;
+                    }
+                }
+            }
+            match next {
+                Some(i) => {
+                    let mod_name = self.research_queue.remove(i);
+                    self.start_research_into(&mod_name, start_ts)?;
+                },
+                None => break
+            }
         }
+        Ok(())
     }
 
     fn get_potential_modifiers(&self) -> Option<HashMap<&String, &Modifier>> {
         let game = &self.game;
-        if game.modifiers.len() > 0 {
+        if !game.modifiers.is_empty() {
             let mut potential_modifiers: HashMap<&String, &Modifier> = HashMap::new();
             for (mod_name, modifier) in &game.modifiers {
 
-                let mut ok = true;
-                if !modifier.locked_by.is_empty() {
-                    for locking_mod_name in &modifier.locked_by {
-                        if !self.active_modifiers.contains(locking_mod_name) {
-                            ok = false;
-                            break;
-                        }
-                    }
-                }
-
-                if ok {
+                if self.is_unlocked(modifier) && !self.is_maxed(modifier) {
                     potential_modifiers.insert(mod_name, modifier);
                 }
             }
 
-            if potential_modifiers.len() > 0 {
+            if !potential_modifiers.is_empty() {
                 Some(potential_modifiers)
             }
             else {
@@ -109,206 +240,490 @@ impl State {
         }
     }
 
-    pub fn list_potential_modifiers(&self) {
-        match self.get_potential_modifiers() {
-            Some(mods) => {
-                println!("Potential modifiers:");
-                for (mod_name, modifier) in mods {
-                    println!(" - {}: {}", mod_name, modifier.description);
-                }
-            },
-            None => {
-                println!("No potential modifiers.")
+    pub(crate) fn is_owned(&self, mod_name: &str) -> bool {
+        self.active_modifiers.iter().any(|n| n == mod_name)
+    }
+
+    pub(crate) fn is_in_progress(&self, mod_name: &str) -> bool {
+        self.modifiers_in_progress.iter().any(|r| r.name == mod_name)
+    }
+
+    // Owned, being researched or queued.
+    pub(crate) fn is_chosen(&self, mod_name: &str) -> bool {
+        self.is_owned(mod_name) || self.is_in_progress(mod_name) || self.research_queue.iter().any(|n| n == mod_name)
+    }
+
+    pub(crate) fn is_unlocked(&self, modifier: &Modifier) -> bool {
+        let owned = |n: &str| self.is_owned(n);
+        let chosen = |n: &str| self.is_chosen(n);
+        !self.locked_out.contains(&modifier.name)
+            && modifier.locked_by.iter().all(|p| p.is_met(&owned, &chosen))
+    }
+
+    // Research started on one branch locks out its rivals for good, even if
+    // that research is later cancelled.
+    fn lock_out_rivals(&mut self) {
+        let committed = |n: &str| self.is_owned(n) || self.is_in_progress(n);
+        let mut rivals: Vec<String> = Vec::new();
+        for (mod_name, modifier) in &self.game.modifiers {
+            if !committed(mod_name) && !self.locked_out.contains(mod_name)
+                && modifier.locked_by.iter().any(|p| p.is_excluded(&committed)) {
+                rivals.push(mod_name.clone());
             }
         }
+
+        rivals.sort();
+        self.research_queue.retain(|n| !rivals.contains(n));
+        self.locked_out.extend(rivals);
     }
 
-    fn start_research_into(&mut self, mod_name: &String) {
-        let modifier = self.game.modifiers.get(&mod_name.to_string()).unwrap();
-        let now_ts = Utc::now().naive_utc().timestamp();
-        let dt_ts = modifier.get_time_cost();
-        let end_ts = now_ts + dt_ts;
-        self.modifiers_in_progress.push((mod_name.to_string(), end_ts));
+    // Levels of a modifier owned, being researched or queued.
+    fn levels_taken(&self, mod_name: &str) -> usize {
+        self.active_modifiers.iter().filter(|n| *n == mod_name).count()
+            + self.modifiers_in_progress.iter().filter(|r| r.name == mod_name).count()
+            + self.research_queue.iter().filter(|n| *n == mod_name).count()
     }
 
-    pub fn try_research_modifier(&mut self, mod_name: &str) {
-        match self.get_potential_modifiers() {
-            Some(mods) => {
-                let sr_mod_name = &mod_name.to_string();
-                if mods.contains_key(sr_mod_name) {
-                    self.start_research_into(sr_mod_name);
-                    println!("Starting research into \"{}\"", sr_mod_name);
-                }
-                else {
-                    if self.game.modifiers.contains_key(sr_mod_name) {
-                        let modif = self.game.modifiers.get(sr_mod_name).unwrap();
-                        println!("Cannot research \"{}\", as it is locked", sr_mod_name);
-                    }
-                    else {
-                        println!("Cannot research \"{}\", no such modifier!", sr_mod_name);
-                    }
+    pub(crate) fn is_maxed(&self, modifier: &Modifier) -> bool {
+        self.levels_taken(&modifier.name) >= modifier.max_level
+    }
+
+    pub(crate) fn research_cost(&self, modifier: &Modifier) -> Result<ResearchCost, GreyGooError> {
+        let stats = self.get_stats()?;
+        let mut time = modifier.get_time_cost()? as f64 / stats.research_speed;
+        let mut inspiration = modifier.inspiration_cost * stats.research_cost;
+        for effect in self.active_effects()? {
+            for tag in &modifier.tags {
+                if let Some(scaling) = effect.research_by_tag.get(tag) {
+                    time *= scaling.time_mult;
+                    inspiration *= scaling.cost_mult;
                 }
-            },
-            None => {
-                println!("No modifiers are available to research at the moment.");
             }
         }
+        Ok(ResearchCost { time: time.max(0f64).round() as i64, inspiration: inspiration.max(0f64) })
     }
 
-    fn get_trial(&self) -> Result<Trial, ()> {
-        let stats = self.get_stats();
-        Ok(Trial::new(stats))
+    fn can_afford(&self, modifier: &Modifier) -> Result<bool, GreyGooError> {
+        Ok(self.has_inspiration(self.research_cost(modifier)?.inspiration))
     }
 
-    pub fn start_trial(&mut self) {
-        if self.trial_in_progress.is_some() {
-            println!("Cannot start a new trial while another is in progress.");
+    fn has_inspiration(&self, amount: f64) -> bool {
+        reaches(self.scientific_inspiration, amount)
+    }
+
+    // Unlocked once everything already owned, researching or queued completes.
+    fn will_be_unlocked(&self, modifier: &Modifier) -> bool {
+        let chosen = |n: &str| self.is_chosen(n);
+        !self.locked_out.contains(&modifier.name)
+            && modifier.locked_by.iter().all(|p| p.is_met(&chosen, &chosen))
+    }
+
+    pub fn list_potential_modifiers(&self) -> Result<Vec<ResearchOption>, GreyGooError> {
+        let mut mods: Vec<&Modifier> = match self.get_potential_modifiers() {
+            Some(mods) => mods.into_values().collect(),
+            None => Vec::new()
+        };
+        mods.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut options: Vec<ResearchOption> = Vec::new();
+        for modifier in mods {
+            let cost = self.research_cost(modifier)?;
+            options.push(ResearchOption {
+                name: modifier.name.clone(),
+                description: modifier.description.clone(),
+                time_cost: cost.time,
+                inspiration_cost: cost.inspiration,
+                base_time_cost: modifier.get_time_cost()?,
+                base_inspiration_cost: modifier.inspiration_cost,
+                affordable: self.has_inspiration(cost.inspiration),
+                level: self.levels_taken(&modifier.name) + 1,
+                max_level: modifier.max_level
+            });
         }
-        else if let Ok(trial) = self.get_trial() {
-            let _ = self.trial_in_progress.insert(trial);
-            self.scientific_inspiration += 0.5f64;
-            println!("New trial begun!")
+        Ok(options)
+    }
+
+    fn start_research_into(&mut self, mod_name: &String, start_ts: i64) -> Result<ResearchStarted, GreyGooError> {
+        let modifier = self.game.modifiers.get(&mod_name.to_string())
+            .ok_or_else(|| GreyGooError::UnknownModifier(mod_name.to_string()))?;
+        let cost = self.research_cost(modifier)?;
+        let end_ts = start_ts + cost.time;
+        let inspiration_spent = cost.inspiration;
+        self.scientific_inspiration -= inspiration_spent;
+        self.modifiers_in_progress.push(Research {
+            name: mod_name.to_string(),
+            start_ts,
+            end_ts,
+            inspiration_spent
+        });
+        self.lock_out_rivals();
+        self.record(start_ts, LogEvent::ResearchStarted { name: mod_name.to_string(), inspiration_spent });
+        Ok(ResearchStarted { name: mod_name.to_string(), end_ts, inspiration_spent })
+    }
+
+    pub fn try_research_modifier(&mut self, mod_name: &str) -> Result<ResearchOutcome, GreyGooError> {
+        let sr_mod_name = &mod_name.to_string();
+        let modifier = self.game.modifiers.get(sr_mod_name)
+            .ok_or_else(|| GreyGooError::UnknownModifier(mod_name.to_string()))?;
+
+        let reason = if self.is_maxed(modifier) {
+            RejectReason::AtMaxLevel { max_level: modifier.max_level }
         }
-        else {
-            println!("Could not start trial - something went wrong!");
+        else if !self.is_unlocked(modifier) {
+            RejectReason::Locked(self.explain_lock(modifier)?)
+        }
+        else if !self.can_afford(modifier)? {
+            let cost = self.research_cost(modifier)?.inspiration;
+            RejectReason::CannotAfford { cost, missing: cost - self.scientific_inspiration }
         }
+        else if self.free_research_slots()? == 0 {
+            RejectReason::NoFreeSlots
+        }
+        else {
+            let started = self.start_research_into(sr_mod_name, self.clock.now())?;
+            return Ok(ResearchOutcome::Started(started));
+        };
+        Ok(ResearchOutcome::Rejected(ResearchRejected { name: mod_name.to_string(), reason }))
     }
 
-    pub fn stop_trial(&mut self) {
-        self.stop_or_fail_trial(false);
-    }
+    pub fn queue_research_modifier(&mut self, mod_name: &str) -> Result<ResearchOutcome, GreyGooError> {
+        let modifier = self.game.modifiers.get(mod_name)
+            .ok_or_else(|| GreyGooError::UnknownModifier(mod_name.to_string()))?;
 
-    fn stop_or_fail_trial(&mut self, is_failure: bool) {
-        if self.trial_in_progress.is_some() {
-            let bot_mass = self.trial_in_progress.as_ref().unwrap().bot_mass;
-            let fmt_bot_mass = fmt_mass(bot_mass);
-            let t_wasted = self.trial_in_progress.as_ref().unwrap().get_current_time_progress() as i64;
-            let fmt_t_wasted = fmt_t(t_wasted);
-            self.trial_in_progress = None;
-            let stopped = if is_failure { "failed" } else { "stopped" };
-            println!("Trial {}. {} of bots were silenced. {} of research time, wasted.", stopped, fmt_bot_mass, fmt_t_wasted);
+        if self.is_maxed(modifier) {
+            Ok(ResearchOutcome::Rejected(ResearchRejected {
+                name: mod_name.to_string(),
+                reason: RejectReason::AtMaxLevel { max_level: modifier.max_level }
+            }))
+        }
+        else if self.will_be_unlocked(modifier) {
+            // Research blocked further up the queue doesn't hold this up
+            let queued_before = self.research_queue.iter().filter(|n| *n == mod_name).count();
+            self.research_queue.push(mod_name.to_string());
+            self.start_queued_research(self.clock.now())?;
+
+            let still_queued = self.research_queue.iter().filter(|n| *n == mod_name).count();
+            let started = match self.modifiers_in_progress.iter().rev().find(|r| r.name == mod_name) {
+                Some(research) if still_queued == queued_before => Some(research),
+                _ => None
+            };
+            match started {
+                Some(research) => Ok(ResearchOutcome::Started(ResearchStarted {
+                    name: research.name.clone(),
+                    end_ts: research.end_ts,
+                    inspiration_spent: research.inspiration_spent
+                })),
+                None => Ok(ResearchOutcome::Queued(ResearchQueued {
+                    name: mod_name.to_string(),
+                    position: self.research_queue.iter().rposition(|n| n == mod_name).map_or(0, |i| i + 1)
+                }))
+            }
         }
         else {
-            let stop = if is_failure { "mark as failed" } else { "stop" };
-            println!("No active trial to {}.", stop);
+            Ok(ResearchOutcome::Rejected(ResearchRejected {
+                name: mod_name.to_string(),
+                reason: RejectReason::Locked(self.explain_lock(modifier)?)
+            }))
+        }
+    }
+
+    pub fn cancel_research(&mut self, mod_name: &str) -> Result<Option<ResearchCancelled>, GreyGooError> {
+        if !self.game.modifiers.contains_key(mod_name) {
+            return Err(GreyGooError::UnknownModifier(mod_name.to_string()));
+        }
+
+        // Nothing has been spent on queued research yet.
+        if let Some(i) = self.research_queue.iter().position(|n| n == mod_name) {
+            let now = self.clock.now();
+            self.research_queue.remove(i);
+            self.record(now, LogEvent::ResearchCancelled { name: mod_name.to_string(), was_queued: true, refund: 0f64 });
+            let dropped = self.drop_stranded_research(now);
+            return Ok(Some(ResearchCancelled { name: mod_name.to_string(), was_queued: true, refund: 0f64, dropped }));
+        }
+
+        match self.modifiers_in_progress.iter().position(|r| r.name == mod_name) {
+            Some(i) => {
+                let now = self.clock.now();
+                let (name, refund) = self.abandon_research(i, now)?;
+                let dropped = self.drop_stranded_research(now);
+                self.start_queued_research(now)?;
+                Ok(Some(ResearchCancelled { name, was_queued: false, refund, dropped }))
+            },
+            None => Ok(None)
         }
     }
 
-    pub fn check_research_progress(&self, loud: bool) {
-        if self.modifiers_in_progress.len() > 0 {
-            let now_ts = Utc::now().naive_utc().timestamp();
-            println!("Research in progress:");
-            for (n, e_ts) in &self.modifiers_in_progress {
-                let fmt_ttgo = fmt_t(e_ts - now_ts);
-                println!(" - {} ({} to go)", n, fmt_ttgo);
+    // Stops research in progress, refunding part of what was spent on it in
+    // proportion to the time left. Returns its name and the refund, which is
+    // only what fits under the cap.
+    fn abandon_research(&mut self, i: usize, ts: i64) -> Result<(String, f64), GreyGooError> {
+        let research = self.modifiers_in_progress.remove(i);
+        let cap = self.get_stats()?.inspiration_cap;
+        let before = self.scientific_inspiration;
+        self.gain_inspiration(research.inspiration_spent
+            * self.game.cancel_refund_fraction
+            * research.fraction_remaining(ts), cap);
+        let refund = self.scientific_inspiration - before;
+        self.record(ts, LogEvent::ResearchCancelled { name: research.name.clone(), was_queued: false, refund });
+        Ok((research.name, refund))
+    }
+
+    // Queued research that relied on something no longer chosen can never
+    // start, and would keep its rivals locked, so it is dropped too.
+    fn drop_stranded_research(&mut self, ts: i64) -> Vec<String> {
+        let mut dropped: Vec<String> = Vec::new();
+        loop {
+            let stranded = self.research_queue.iter().position(|n| {
+                self.game.modifiers.get(n).map_or(true, |m| !self.is_unlocked(m))
+            });
+            match stranded {
+                Some(i) => {
+                    let mod_name = self.research_queue.remove(i);
+                    self.record(ts, LogEvent::ResearchCancelled { name: mod_name.clone(), was_queued: true, refund: 0f64 });
+                    dropped.push(mod_name);
+                },
+                None => return dropped
             }
         }
+    }
+
+    pub fn start_trial(&mut self) -> Result<TrialStartOutcome, GreyGooError> {
+        if self.trial_in_progress.is_some() {
+            Ok(TrialStartOutcome::AlreadyRunning)
+        }
         else {
-            if loud {
-                println!("No research in progress.");
-            }
+            let stats = self.get_stats()?;
+            let trial = Trial::new(&stats, self.clock.as_ref());
+            let initial_bot_mass = trial.bot_mass;
+            let start_ts = trial.start_ts;
+            let _ = self.trial_in_progress.insert(trial);
+            self.gain_inspiration(0.5f64, stats.inspiration_cap);
+            self.record(start_ts, LogEvent::TrialStarted { initial_bot_mass });
+            Ok(TrialStartOutcome::Started { initial_bot_mass })
         }
     }
 
-    pub fn check_trial_progress(&mut self, loud: bool) {
+    pub fn stop_trial(&mut self) -> Result<Option<TrialEnded>, GreyGooError> {
+        let reliability = self.get_stats()?.self_destruct_reliability;
+        let mut ended = self.end_trial(TrialOutcome::Stopped, 1f64 - reliability);
+        let now = self.clock.now();
+        let consequences = self.check_unease_bands(now)?;
+        if let Some(ended) = ended.as_mut() {
+            ended.consequences = consequences;
+        }
+        Ok(ended)
+    }
+
+    // Bots that escape an unreliable self-destruct alarm the population.
+    fn end_trial(&mut self, outcome: TrialOutcome, escaped_fraction: f64) -> Option<TrialEnded> {
+        let trial = self.trial_in_progress.take()?;
+        // A trial that was won, lost or halted stopped when that happened
+        let end_ts = match outcome {
+            TrialOutcome::Stopped => self.clock.now(),
+            _ => trial.last_update_ts
+        };
+        let escaped_bot_mass = trial.bot_mass * escaped_fraction;
+        self.population_unease = (self.population_unease + escaped_fraction * self.game.escape_unease).min(100f64);
+        self.record(end_ts, LogEvent::TrialEnded { outcome: outcome.clone(), bot_mass: trial.bot_mass, escaped_bot_mass });
+        Some(TrialEnded {
+            outcome,
+            bot_mass: trial.bot_mass,
+            escaped_bot_mass,
+            elapsed: end_ts - trial.start_ts,
+            consequences: Vec::new()
+        })
+    }
+
+    pub fn check_research_progress(&self) -> Vec<ResearchProgress> {
+        let now_ts = self.clock.now();
+        self.modifiers_in_progress.iter()
+            .map(|r| ResearchProgress { name: r.name.to_string(), remaining: r.end_ts - now_ts })
+            .collect()
+    }
+
+    pub fn check_trial_progress(&mut self) -> TrialReport {
         match &self.trial_in_progress {
             Some(trial) => {
                 match trial.get_status(&self.game) {
                     TrialStatus::Failure => {
-                        self.stop_or_fail_trial(true);
+                        TrialReport::Ended(self.end_trial(TrialOutcome::Failed, 0f64).unwrap())
                     },
                     TrialStatus::Success => {
-                        println!("Trial success! You win!");
-                        self.trial_in_progress = None;
+                        TrialReport::Ended(self.end_trial(TrialOutcome::Succeeded, 0f64).unwrap())
+                    },
+                    TrialStatus::Halted(event_name) => {
+                        TrialReport::Ended(self.end_trial(TrialOutcome::Halted(event_name), 0f64).unwrap())
                     },
                     TrialStatus::InProgress(bot_mass) => {
-                        if loud {
-                            let fmt_bot_mass = fmt_mass(bot_mass);
-                            let pc = 100f64 * bot_mass / self.game.world_mass;
-                            let t_elapsed = trial.get_current_time_progress();
-                            let fmt_t_elapsed = fmt_t(t_elapsed as i64);
-                            // let rising_ind = if trial.is_rising() {
-                            //     "📈"
-                            // }
-                            // else {
-                            //     "📉"
-                            // };
-                            // trial.plot();
-                            println!("Trial running // Bots totalling {} (~{:.1}% domination) // {} elapsed", fmt_bot_mass, pc, fmt_t_elapsed);
+                        TrialReport::Running {
+                            bot_mass,
+                            domination: bot_mass / self.game.world_mass,
+                            elapsed: trial.get_current_time_progress(self.clock.as_ref()) as i64
                         }
                     }
                 }
             },
-            None => {
-                println!("No trial in progress.")
-            }
+            None => TrialReport::NoTrial
         }
     }
 
-    pub fn get_stats(&self) -> Stats {
-        let mut stats = Stats{
-            initial_bot_mass: 1f64,
-            growth_rate: 1f64,
-            death_rate: 1f64,
-
-            unease_gain: 0.01f64,
-            inspiration_gain: 0.01f64
-        };
-
+    // Effects of every owned modifier level.
+    fn active_effects(&self) -> Result<Vec<&Effect>, GreyGooError> {
+        let mut effects: Vec<&Effect> = Vec::new();
         for mod_name in &self.active_modifiers {
             if let Some(modifier) = self.game.modifiers.get(mod_name.as_str()) {
-                if let Some(effect) = modifier.get_effect(self) {
-                    stats.initial_bot_mass *= effect.initial_mass_mult;
-                    stats.growth_rate *= effect.growth_rate_mult;
-                    stats.death_rate *= effect.death_rate_mult;
-                    stats.unease_gain *= effect.unease_gain_mult;
-                    stats.inspiration_gain *= effect.inspiration_gain_mult;
-                }
+                effects.extend(modifier.get_effects(self)?);
             }
         }
+        Ok(effects)
+    }
 
-        stats
+    pub fn get_stats(&self) -> Result<Stats, GreyGooError> {
+        let effects = self.active_effects()?;
+        let stat = |stat: Stat, base: f64| apply_effects(stat, base, &effects);
+        Ok(Stats {
+            initial_bot_mass: stat(Stat::InitialMass, 1f64),
+            growth_rate: stat(Stat::GrowthRate, 1f64),
+            death_rate: stat(Stat::DeathRate, 1f64),
+            unease_gain: stat(Stat::UneaseGain, 0.01f64),
+            unease_decay: stat(Stat::UneaseDecay, self.game.unease_decay).max(0f64),
+            inspiration_gain: stat(Stat::InspirationGain, 0.01f64),
+            inspiration_cap: stat(Stat::InspirationCap, self.game.inspiration_cap.unwrap_or(f64::INFINITY)),
+            // research never stops entirely
+            research_speed: stat(Stat::ResearchSpeed, 1f64).max(0.01f64),
+            research_cost: stat(Stat::ResearchCost, 1f64).max(0f64),
+            research_slots: stat(Stat::ResearchSlots, self.game.research_slots as f64).max(0f64).round() as usize,
+            self_destruct_reliability: stat(Stat::SelfDestructReliability, 1f64).clamp(0f64, 1f64)
+        })
     }
 
+    // Inspiration gained beyond the cap is lost, but inspiration already
+    // over it (e.g. from a difficulty's starting bonus) is kept.
+    fn gain_inspiration(&mut self, amount: f64, cap: f64) {
+        if self.scientific_inspiration < cap {
+            self.scientific_inspiration = (self.scientific_inspiration + amount).min(cap);
+        }
+    }
 
-    pub fn update_trial(&mut self) -> Vec<String> {
-        let mut events: Vec<String> = Vec::new();
+    // Events whose conditions hold fire as independent Poisson processes, so
+    // the time to the next one is exponentially distributed with the total
+    // rate, and which one it is is chosen in proportion to the rates.
+    pub(crate) fn get_next_event(&mut self) -> Result<Option<PendingEvent>, GreyGooError> {
+        let mut event_names: Vec<&String> = self.game.events.keys().collect();
+        event_names.sort();
+
+        let mut rates: Vec<(String, f64)> = Vec::new();
+        for event_name in event_names {
+            let event = &self.game.events[event_name];
+            if event.condition.get().map_or(false, |c| c.evaluate(self)) {
+                rates.push((event_name.clone(), 1f64 / event.get_mean_interval()? as f64));
+            }
+        }
 
-        if self.trial_in_progress.is_some() {
-            let current_ts = Utc::now().timestamp();
+        let total_rate: f64 = rates.iter().map(|(_, rate)| rate).sum();
+        let trial = match self.trial_in_progress.as_mut() {
+            Some(trial) if total_rate > 0f64 => trial,
+            _ => return Ok(None)
+        };
 
-            // find out when the next event will run and what it will be
-            let (mut next_event_dt, mut next_event) = self.get_next_event();
-            let mut next_event_ts = self.trial_in_progress.as_ref().unwrap().last_update_ts + next_event_dt;
+        let mut rng = Rng::new(trial.rng);
+        let dt = (-rng.next_f64().ln() / total_rate).ceil().max(1f64) as i64;
+        let mut pick = rng.next_f64() * total_rate;
+        trial.rng = rng.state;
 
-            while next_event_ts < current_ts {
-                // run up to when the event should fire
-                self.update_trial_until(next_event_ts);
+        let mut name = rates[rates.len() - 1].0.clone();
+        for (event_name, rate) in rates {
+            if pick <= rate {
+                name = event_name;
+                break;
+            }
+            pick -= rate;
+        }
 
-                // run event, note it down too.
-                events.push(next_event.clone());
-                // self.run_event(&next_event);
+        Ok(Some(PendingEvent { name, ts: trial.last_update_ts + dt }))
+    }
 
-                // find out when the next event will run and what it will be
-                (next_event_dt, next_event) = self.get_next_event();
-                next_event_ts = self.trial_in_progress.as_ref().unwrap().last_update_ts + next_event_dt;
-            };
+    // Applies the event's outcome, unless its condition stopped holding since
+    // it was scheduled.
+    pub(crate) fn run_event(&mut self, pending: &PendingEvent) -> Result<Option<EventFired>, GreyGooError> {
+        let event = match self.game.events.get(&pending.name) {
+            Some(event) => event,
+            None => return Ok(None)
+        };
+        if !event.condition.get().map_or(false, |c| c.evaluate(self)) {
+            return Ok(None);
+        }
+
+        let fired = EventFired { name: event.name.clone(), description: event.description.clone(), ts: pending.ts };
+        let outcome = event.outcome.clone();
+        self.apply_outcome(&outcome, &fired.name, pending.ts)?;
+        Ok(Some(fired))
+    }
 
-            let trial = self.trial_in_progress.as_mut().unwrap();
-            if trial.last_update_ts < current_ts {
-                self.update_trial_until(current_ts);
+    // `cause` is what the trial was halted by, if the outcome ends it, and
+    // `ts` when it happened.
+    pub(crate) fn apply_outcome(&mut self, outcome: &EventOutcome, cause: &str, ts: i64) -> Result<(), GreyGooError> {
+        if let Some(trial) = self.trial_in_progress.as_mut() {
+            trial.bot_mass *= outcome.bot_mass_mult;
+            if outcome.end_trial {
+                trial.halted_by = Some(cause.to_string());
             }
         }
 
-        events
+        self.population_unease = (self.population_unease + outcome.unease_add).clamp(0f64, 100f64);
+
+        if outcome.inspiration_add > 0f64 {
+            let cap = self.get_stats()?.inspiration_cap;
+            self.gain_inspiration(outcome.inspiration_add, cap);
+        }
+        else {
+            self.scientific_inspiration = (self.scientific_inspiration + outcome.inspiration_add).max(0f64);
+        }
+
+        if let Some(mod_name) = &outcome.grant_modifier {
+            self.grant_modifier(mod_name)?;
+        }
+
+        if let Some(mod_name) = &outcome.block_modifier {
+            self.block_modifier(mod_name, ts)?;
+        }
+
+        Ok(())
     }
 
-    fn get_next_event(&self) -> (i64, String) {
-        (1_000_000i64, "foo".to_string())
+    // A free level of a modifier, taking the place of any queued research into it.
+    fn grant_modifier(&mut self, mod_name: &str) -> Result<(), GreyGooError> {
+        let modifier = self.game.modifiers.get(mod_name)
+            .ok_or_else(|| GreyGooError::UnknownModifier(mod_name.to_string()))?;
+        if let Some(i) = self.research_queue.iter().position(|n| n == mod_name) {
+            self.research_queue.remove(i);
+        }
+        if !self.is_maxed(modifier) && !self.locked_out.iter().any(|n| n == mod_name) {
+            self.active_modifiers.push(mod_name.to_string());
+            self.lock_out_rivals();
+        }
+        Ok(())
+    }
+
+    // Locked out for the rest of the game, abandoning any research into it as
+    // if it had been cancelled.
+    fn block_modifier(&mut self, mod_name: &str, ts: i64) -> Result<(), GreyGooError> {
+        while let Some(i) = self.research_queue.iter().position(|n| n == mod_name) {
+            self.research_queue.remove(i);
+            self.record(ts, LogEvent::ResearchCancelled { name: mod_name.to_string(), was_queued: true, refund: 0f64 });
+        }
+        while let Some(i) = self.modifiers_in_progress.iter().position(|r| r.name == mod_name) {
+            self.abandon_research(i, ts)?;
+        }
+        if !self.locked_out.iter().any(|n| n == mod_name) {
+            self.locked_out.push(mod_name.to_string());
+        }
+        self.drop_stranded_research(ts);
+        self.start_queued_research(ts)
     }
 
-    fn update_trial_until(&mut self, until_ts: i64) {
-        let stats = self.get_stats();
+    pub(crate) fn update_trial_until(&mut self, until_ts: i64) -> Result<(), GreyGooError> {
+        let stats = self.get_stats()?;
         if let Some(trial) = self.trial_in_progress.as_mut() {
             let dt = (until_ts - trial.last_update_ts) as f64;
 
@@ -322,10 +737,13 @@ impl State {
             }
 
             // Gain inspiration (also linear)
-            self.scientific_inspiration += stats.inspiration_gain * (dt / self.game.tau);
+            let inspiration_gain = stats.inspiration_gain * (dt / self.game.tau);
 
             // mark update time
             trial.last_update_ts = until_ts;
+
+            self.gain_inspiration(inspiration_gain, stats.inspiration_cap);
         }
+        Ok(())
     }
 }
\ No newline at end of file
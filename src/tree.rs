@@ -0,0 +1,92 @@
+use crate::error::GreyGooError;
+use crate::state::State;
+
+pub enum NodeStatus {
+    Owned,
+    InProgress,
+    Queued,
+    Available,
+    Locked,
+    LockedOut
+}
+
+pub struct TreeNode {
+    pub name: String,
+    pub status: NodeStatus,
+    pub time_cost: i64
+}
+
+// `from` is a prerequisite of `to`; `negated` if `to` requires `from` not be chosen.
+pub struct TreeEdge {
+    pub from: String,
+    pub to: String,
+    pub negated: bool,
+    pub time_cost: i64
+}
+
+pub struct ResearchTree {
+    pub nodes: Vec<TreeNode>,
+    pub edges: Vec<TreeEdge>
+}
+
+impl ResearchTree {
+    // Names of modifiers that need nothing researched first.
+    pub fn roots(&self) -> Vec<&str> {
+        self.nodes.iter()
+            .filter(|node| !self.edges.iter().any(|e| e.to == node.name && !e.negated))
+            .map(|node| node.name.as_str())
+            .collect()
+    }
+
+    pub fn children(&self, name: &str) -> Vec<&TreeEdge> {
+        self.edges.iter().filter(|e| e.from == name && !e.negated).collect()
+    }
+
+    pub fn node(&self, name: &str) -> Option<&TreeNode> {
+        self.nodes.iter().find(|node| node.name == name)
+    }
+}
+
+impl State {
+    pub fn research_tree(&self) -> Result<ResearchTree, GreyGooError> {
+        let mut names: Vec<&String> = self.game.modifiers.keys().collect();
+        names.sort();
+
+        let mut nodes: Vec<TreeNode> = Vec::new();
+        let mut edges: Vec<TreeEdge> = Vec::new();
+        for name in names {
+            let modifier = &self.game.modifiers[name];
+            let time_cost = self.research_cost(modifier)?.time;
+
+            let status = if self.is_owned(name) {
+                NodeStatus::Owned
+            }
+            else if self.is_in_progress(name) {
+                NodeStatus::InProgress
+            }
+            else if self.research_queue.contains(name) {
+                NodeStatus::Queued
+            }
+            else if self.locked_out.contains(name) {
+                NodeStatus::LockedOut
+            }
+            else if self.is_unlocked(modifier) && !self.is_maxed(modifier) {
+                NodeStatus::Available
+            }
+            else {
+                NodeStatus::Locked
+            };
+            nodes.push(TreeNode { name: name.clone(), status, time_cost });
+
+            for prerequisite in &modifier.locked_by {
+                prerequisite.walk(false, &mut |from, negated| {
+                    if !edges.iter().any(|e| e.from == from && e.to == *name && e.negated == negated) {
+                        edges.push(TreeEdge { from: from.to_string(), to: name.clone(), negated, time_cost });
+                    }
+                });
+            }
+        }
+
+        Ok(ResearchTree { nodes, edges })
+    }
+}
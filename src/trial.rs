@@ -1,8 +1,6 @@
-use chrono::Utc;
-
 use serde::{Deserialize, Serialize};
-use textplots::{Chart, Plot, Shape};
 
+use crate::clock::Clock;
 use crate::game::Game;
 use crate::state::Stats;
 // use crate::modifier::Modifier;
@@ -11,7 +9,15 @@ use crate::state::Stats;
 pub enum TrialStatus {
     InProgress(f64),
     Success,
-    Failure
+    Failure,
+    // Ended by the named event
+    Halted(String)
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct PendingEvent {
+    pub name: String,
+    pub ts: i64
 }
 
 #[derive(Deserialize, Serialize)]
@@ -22,20 +28,37 @@ pub struct Trial {
     // Timings
     pub start_ts: i64,
     pub last_update_ts: i64,
+
+    // Events
+    #[serde(default)]
+    pub rng: u64,
+
+    #[serde(default)]
+    pub next_event: Option<PendingEvent>,
+
+    // Set when an event ends the trial, until the player next checks in
+    #[serde(default)]
+    pub halted_by: Option<String>
 }
 
 impl Trial {
-    pub fn new(stats: Stats) -> Trial {
-        let start_ts = Utc::now().timestamp();
+    pub fn new(stats: &Stats, clock: &dyn Clock) -> Trial {
+        let start_ts = clock.now();
         let last_update_ts = start_ts;
 
         Trial {
-            bot_mass: stats.initial_bot_mass, start_ts, last_update_ts
+            bot_mass: stats.initial_bot_mass, start_ts, last_update_ts,
+            rng: start_ts as u64,
+            next_event: None,
+            halted_by: None
         }
     }
 
     pub fn get_status(&self, game: &Game) -> TrialStatus {
-        if self.bot_mass >= game.world_mass {
+        if let Some(event_name) = &self.halted_by {
+            TrialStatus::Halted(event_name.clone())
+        }
+        else if self.bot_mass >= game.world_mass {
             TrialStatus::Success
         }
         else if self.bot_mass <= 0f64 {
@@ -53,10 +76,9 @@ impl Trial {
     //     self.initial_bot_count * a.powf(dt / TAU)
     // }
     //
-    pub fn get_current_time_progress(&self) -> f32 {
-        let now_ts = Utc::now().naive_utc().timestamp();
-        let now_dt = (now_ts - self.start_ts) as f32;
-        now_dt
+    pub fn get_current_time_progress(&self, clock: &dyn Clock) -> f32 {
+        let now_ts = clock.now();
+        (now_ts - self.start_ts) as f32
     }
     //
     // pub fn get_current_time_progress_frac(&self) -> f32 {
@@ -0,0 +1,59 @@
+use serde::Deserialize;
+
+use crate::error::GreyGooError;
+use crate::event::EventOutcome;
+use crate::log::LogEvent;
+use crate::outcome::EventFired;
+use crate::state::{reaches, State};
+
+// What the public does once population unease reaches a threshold, e.g.
+// protests at the lab gates. Each band fires once on the way up, and again
+// only after unease has fallen back below it.
+#[derive(Deserialize)]
+pub struct UneaseBand {
+    pub name: String,
+    pub description: String,
+    pub threshold: f64,
+
+    #[serde(default)]
+    pub outcome: EventOutcome
+}
+
+impl State {
+    // The highest band unease is currently in, if any.
+    pub fn unease_band(&self) -> Option<&UneaseBand> {
+        self.game.unease_bands.iter()
+            .take_while(|b| self.in_band(b))
+            .last()
+    }
+
+    fn bands_reached(&self) -> usize {
+        self.game.unease_bands.iter()
+            .take_while(|b| self.in_band(b))
+            .count()
+    }
+
+    fn in_band(&self, band: &UneaseBand) -> bool {
+        reaches(self.population_unease, band.threshold)
+    }
+
+    // Sets off every band unease has risen into since the last check, lowest
+    // first, and re-arms those it has since fallen out of.
+    pub(crate) fn check_unease_bands(&mut self, ts: i64) -> Result<Vec<EventFired>, GreyGooError> {
+        let mut fired: Vec<EventFired> = Vec::new();
+        while self.unease_bands_fired < self.bands_reached() {
+            let band = &self.game.unease_bands[self.unease_bands_fired];
+            let name = band.name.clone();
+            let description = band.description.clone();
+            let outcome = band.outcome.clone();
+
+            self.unease_bands_fired += 1;
+            self.apply_outcome(&outcome, &name, ts)?;
+            self.record(ts, LogEvent::UneaseConsequence { name: name.clone(), description: description.clone() });
+            fired.push(EventFired { name, description, ts });
+        }
+        // The outcome itself may have calmed things down
+        self.unease_bands_fired = self.unease_bands_fired.min(self.bands_reached());
+        Ok(fired)
+    }
+}
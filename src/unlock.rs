@@ -0,0 +1,186 @@
+use std::collections::HashSet;
+
+use crate::error::GreyGooError;
+use crate::modifier::Modifier;
+use crate::prerequisite::Prerequisite;
+use crate::state::State;
+
+pub enum PrerequisiteStatus {
+    Researching { remaining: i64 },
+    Queued,
+    NotStarted
+}
+
+pub enum UnmetRequirement {
+    Prerequisite { name: String, status: PrerequisiteStatus },
+    Condition(Prerequisite),
+    LockedOut
+}
+
+pub struct LockExplanation {
+    pub unmet: Vec<UnmetRequirement>,
+
+    // False once a rival branch has been chosen, or if every way to it leads
+    // round in a circle: nothing can unlock it.
+    pub reachable: bool,
+
+    // Everything still to research to unlock the modifier, prerequisites first,
+    // and what it would cost if researched one after the other. Where there is
+    // a choice (any_of, at_least) the quickest options are taken.
+    pub path: Vec<String>,
+    pub total_time: i64,
+    pub total_inspiration: f64
+}
+
+impl State {
+    pub fn explain_lock(&self, modifier: &Modifier) -> Result<LockExplanation, GreyGooError> {
+        let owned = |n: &str| self.is_owned(n);
+        let chosen = |n: &str| self.is_chosen(n);
+
+        let mut unmet: Vec<UnmetRequirement> = Vec::new();
+        let locked_out = self.locked_out.contains(&modifier.name);
+        if locked_out {
+            unmet.push(UnmetRequirement::LockedOut);
+        }
+        for prerequisite in &modifier.locked_by {
+            if !prerequisite.is_met(&owned, &chosen) {
+                unmet.push(match prerequisite {
+                    Prerequisite::Modifier(name) => UnmetRequirement::Prerequisite {
+                        name: name.clone(),
+                        status: self.prerequisite_status(name)
+                    },
+                    _ => UnmetRequirement::Condition(prerequisite.clone())
+                });
+            }
+        }
+
+        let mut reachable = !locked_out && !modifier.locked_by.iter().any(|p| p.is_excluded(&chosen));
+        let mut path: Vec<String> = Vec::new();
+        if reachable {
+            let mut visited: HashSet<String> = HashSet::from([modifier.name.clone()]);
+            for prerequisite in &modifier.locked_by {
+                reachable &= self.plan(prerequisite, &mut visited, &mut path);
+            }
+            if !reachable {
+                path.clear();
+            }
+        }
+
+        let mut total_time = 0i64;
+        let mut total_inspiration = 0f64;
+        for name in &path {
+            let prerequisite = self.game.modifiers.get(name)
+                .ok_or_else(|| GreyGooError::UnknownModifier(name.clone()))?;
+            match self.prerequisite_status(name) {
+                PrerequisiteStatus::Researching { remaining } => {
+                    total_time += remaining;
+                },
+                PrerequisiteStatus::Queued | PrerequisiteStatus::NotStarted => {
+                    let cost = self.research_cost(prerequisite)?;
+                    total_time += cost.time;
+                    total_inspiration += cost.inspiration;
+                }
+            }
+        }
+
+        Ok(LockExplanation { unmet, reachable, path, total_time, total_inspiration })
+    }
+
+    fn prerequisite_status(&self, name: &str) -> PrerequisiteStatus {
+        let now = self.clock.now();
+        if let Some(research) = self.modifiers_in_progress.iter().find(|r| r.name == name) {
+            PrerequisiteStatus::Researching { remaining: (research.end_ts - now).max(0) }
+        }
+        else if self.research_queue.iter().any(|n| n == name) {
+            PrerequisiteStatus::Queued
+        }
+        else {
+            PrerequisiteStatus::NotStarted
+        }
+    }
+
+    // Adds what is needed to meet the prerequisite to the path. False if it
+    // can't be met, because every way to it leads back to something already
+    // being planned.
+    fn plan(&self, prerequisite: &Prerequisite, visited: &mut HashSet<String>, path: &mut Vec<String>) -> bool {
+        let owned = |n: &str| self.is_owned(n);
+        let chosen = |n: &str| self.is_chosen(n);
+        if prerequisite.is_met(&owned, &chosen) {
+            return true;
+        }
+
+        match prerequisite {
+            Prerequisite::Modifier(name) => self.collect_path(name, visited, path),
+            Prerequisite::AllOf { all_of } => {
+                let mut met = true;
+                for p in all_of {
+                    met &= self.plan(p, visited, path);
+                }
+                met
+            },
+            Prerequisite::AnyOf { any_of } => self.plan_quickest(any_of, 1, visited, path),
+            Prerequisite::AtLeast { at_least, of } => {
+                let met = of.iter().filter(|p| p.is_met(&owned, &chosen)).count();
+                self.plan_quickest(of, at_least.saturating_sub(met), visited, path)
+            },
+            // Nothing to research that would help.
+            Prerequisite::Not { .. } => true
+        }
+    }
+
+    // Plans the n unmet options that are quickest to meet on their own.
+    fn plan_quickest(&self, options: &[Prerequisite], n: usize, visited: &mut HashSet<String>, path: &mut Vec<String>) -> bool {
+        let owned = |name: &str| self.is_owned(name);
+        let chosen = |name: &str| self.is_chosen(name);
+        let mut timed: Vec<(i64, &Prerequisite)> = options.iter()
+            .filter(|p| !p.is_met(&owned, &chosen) && !p.is_excluded(&chosen))
+            .filter_map(|p| self.time_to_meet(p, visited, path).map(|t| (t, p)))
+            .collect();
+        timed.sort_by_key(|(t, _)| *t);
+
+        let mut met = timed.len() >= n;
+        for (_, p) in timed.into_iter().take(n) {
+            met &= self.plan(p, visited, path);
+        }
+        met
+    }
+
+    // Time to research everything the prerequisite needs that isn't already
+    // planned, or None if it can't be met.
+    fn time_to_meet(&self, prerequisite: &Prerequisite, visited: &HashSet<String>, path: &[String]) -> Option<i64> {
+        let mut visited = visited.clone();
+        let mut path = path.to_vec();
+        let planned = path.len();
+        if !self.plan(prerequisite, &mut visited, &mut path) {
+            return None;
+        }
+        Some(path[planned..].iter()
+            .map(|name| match self.prerequisite_status(name) {
+                PrerequisiteStatus::Researching { remaining } => remaining,
+                _ => self.game.modifiers.get(name).and_then(|m| self.research_cost(m).ok()).map_or(0, |c| c.time)
+            })
+            .sum())
+    }
+
+    // Depth-first, so prerequisites land in the path before what they unlock.
+    // A name visited but not yet in the path is still being planned, so
+    // reaching it again means going round in a circle.
+    fn collect_path(&self, name: &str, visited: &mut HashSet<String>, path: &mut Vec<String>) -> bool {
+        if self.is_owned(name) || path.iter().any(|n| n == name) {
+            return true;
+        }
+        if !visited.insert(name.to_string()) {
+            return false;
+        }
+        let mut met = true;
+        if let Some(modifier) = self.game.modifiers.get(name) {
+            for prerequisite in &modifier.locked_by {
+                met &= self.plan(prerequisite, visited, path);
+            }
+        }
+        if met {
+            path.push(name.to_string());
+        }
+        met
+    }
+}
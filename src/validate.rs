@@ -0,0 +1,274 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::condition::Condition;
+use crate::effect::Stat;
+use crate::event::EventOutcome;
+use crate::game::Game;
+use crate::prerequisite::Prerequisite;
+
+pub struct Issue {
+    // Location of the problem in the game data, e.g. modifiers["foo"].time_cost
+    pub path: String,
+    pub message: String
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Visit {
+    InProgress,
+    Done
+}
+
+pub fn validate_game(game: &Game) -> Vec<Issue> {
+    let mut issues: Vec<Issue> = Vec::new();
+    let mut issue = |path: String, message: String| issues.push(Issue { path, message });
+
+    if game.world_mass <= 0f64 {
+        issue("world_mass".to_string(), "must be greater than zero".to_string());
+    }
+    if game.tau <= 0f64 {
+        issue("tau".to_string(), "must be greater than zero".to_string());
+    }
+
+    if game.research_slots == 0 {
+        issue("research_slots".to_string(), "must be at least 1".to_string());
+    }
+
+    if !(0f64..=1f64).contains(&game.cancel_refund_fraction) {
+        issue("cancel_refund_fraction".to_string(), "must be between 0 and 1".to_string());
+    }
+
+    if game.unease_decay < 0f64 {
+        issue("unease_decay".to_string(), "must not be negative".to_string());
+    }
+
+    if let Some(cap) = game.inspiration_cap {
+        if cap < 0f64 {
+            issue("inspiration_cap".to_string(), "must not be negative".to_string());
+        }
+    }
+
+    if game.escape_unease < 0f64 {
+        issue("escape_unease".to_string(), "must not be negative".to_string());
+    }
+
+    let mut mod_names: Vec<&String> = game.modifiers.keys().collect();
+    mod_names.sort();
+
+    for mod_name in &mod_names {
+        let modifier = &game.modifiers[*mod_name];
+        let path = format!("modifiers[{:?}]", mod_name);
+
+        if let Err(e) = modifier.get_time_cost() {
+            issue(format!("{}.time_cost", path), e.to_string());
+        }
+
+        if modifier.max_level == 0 {
+            issue(format!("{}.max_level", path), "must be at least 1".to_string());
+        }
+
+        for (i, prerequisite) in modifier.locked_by.iter().enumerate() {
+            check_prerequisite(game, prerequisite, format!("{}.locked_by[{}]", path, i), &mut issue);
+        }
+
+        if !modifier.effects.contains_key("default") {
+            issue(format!("{}.effects", path), "no \"default\" effect".to_string());
+        }
+
+        let mut effect_names: Vec<&String> = modifier.effects.keys().collect();
+        effect_names.sort();
+        for effect_name in effect_names {
+            let effect = &modifier.effects[effect_name];
+            let mut clamped: Vec<(&Stat, &f64)> = effect.min.iter().collect();
+            clamped.sort_by_key(|(stat, _)| stat.to_string());
+            for (stat, min) in clamped {
+                if let Some(max) = effect.max.get(stat) {
+                    if min > max {
+                        issue(format!("{}.effects[{:?}].min[{:?}]", path, effect_name, stat.to_string()),
+                              format!("minimum {} is above the maximum {}", min, max));
+                    }
+                }
+            }
+
+            let mut tags: Vec<&String> = effect.research_by_tag.keys().collect();
+            tags.sort();
+            for tag in tags {
+                let scaling = &effect.research_by_tag[tag];
+                let tag_path = format!("{}.effects[{:?}].research_by_tag[{:?}]", path, effect_name, tag);
+                if !game.modifiers.values().any(|m| m.tags.contains(tag)) {
+                    issue(tag_path.clone(), format!("no modifier has the tag \"{}\"", tag));
+                }
+                if scaling.time_mult < 0f64 || scaling.cost_mult < 0f64 {
+                    issue(tag_path, "time_mult and cost_mult must not be negative".to_string());
+                }
+            }
+
+            let effect_path = format!("{}.effects[{:?}].condition", path, effect_name);
+            check_condition(game, &effect.condition.source, effect_path, &mut issue);
+        }
+    }
+
+    // Prerequisite cycles make every modifier in them impossible to research,
+    // unless there is another way in (e.g. a different option of an "any_of").
+    let attainable = attainable_modifiers(game);
+    let mut visits: HashMap<&str, Visit> = HashMap::new();
+    for mod_name in &mod_names {
+        let mut stack: Vec<&str> = Vec::new();
+        find_cycles(game, &attainable, mod_name, &mut visits, &mut stack, &mut issue);
+    }
+
+    let mut event_names: Vec<&String> = game.events.keys().collect();
+    event_names.sort();
+    for event_name in event_names {
+        let event = &game.events[event_name];
+        let path = format!("events[{:?}]", event_name);
+
+        if let Err(e) = event.get_mean_interval() {
+            issue(format!("{}.mean_interval", path), e.to_string());
+        }
+
+        check_condition(game, &event.condition.source, format!("{}.condition", path), &mut issue);
+        check_outcome(game, &event.outcome, format!("{}.outcome", path), &mut issue);
+    }
+
+    for band in &game.unease_bands {
+        let path = format!("unease_bands[{:?}]", band.name);
+        if !(0f64..=100f64).contains(&band.threshold) {
+            issue(format!("{}.threshold", path), "must be between 0 and 100".to_string());
+        }
+        check_outcome(game, &band.outcome, format!("{}.outcome", path), &mut issue);
+    }
+
+    let mut difficulty_names: Vec<&String> = game.difficulties.keys().collect();
+    difficulty_names.sort();
+    for difficulty_name in difficulty_names {
+        let difficulty = &game.difficulties[difficulty_name];
+        for (i, mod_name) in difficulty.starting_modifiers.iter().enumerate() {
+            if !game.modifiers.contains_key(mod_name) {
+                issue(format!("difficulties[{:?}].starting_modifiers[{}]", difficulty_name, i),
+                      format!("unknown modifier \"{}\"", mod_name));
+            }
+        }
+    }
+
+    issues
+}
+
+fn check_outcome(game: &Game, outcome: &EventOutcome, path: String,
+                 issue: &mut impl FnMut(String, String)) {
+    if outcome.bot_mass_mult < 0f64 {
+        issue(format!("{}.bot_mass_mult", path), "must not be negative".to_string());
+    }
+    for (field, mod_name) in [("grant_modifier", &outcome.grant_modifier), ("block_modifier", &outcome.block_modifier)] {
+        if let Some(mod_name) = mod_name {
+            if !game.modifiers.contains_key(mod_name) {
+                issue(format!("{}.{}", path, field), format!("unknown modifier \"{}\"", mod_name));
+            }
+        }
+    }
+}
+
+fn check_condition(game: &Game, source: &str, path: String,
+                   issue: &mut impl FnMut(String, String)) {
+    match Condition::parse(source) {
+        Ok(condition) => {
+            for cond_mod_name in condition.modifier_names() {
+                if !game.modifiers.contains_key(cond_mod_name) {
+                    issue(path.clone(), format!("condition names unknown modifier \"{}\"", cond_mod_name));
+                }
+            }
+        },
+        Err(e) => issue(path, e.to_string())
+    }
+}
+
+fn check_prerequisite(game: &Game, prerequisite: &Prerequisite, path: String,
+                      issue: &mut impl FnMut(String, String)) {
+    match prerequisite {
+        Prerequisite::Modifier(name) => {
+            if !game.modifiers.contains_key(name) {
+                issue(path, format!("unknown prerequisite \"{}\"", name));
+            }
+        },
+        Prerequisite::AnyOf { any_of } => {
+            if any_of.is_empty() {
+                issue(path.clone(), "empty any_of can never be met".to_string());
+            }
+            for (i, p) in any_of.iter().enumerate() {
+                check_prerequisite(game, p, format!("{}.any_of[{}]", path, i), issue);
+            }
+        },
+        Prerequisite::AllOf { all_of } => {
+            for (i, p) in all_of.iter().enumerate() {
+                check_prerequisite(game, p, format!("{}.all_of[{}]", path, i), issue);
+            }
+        },
+        Prerequisite::AtLeast { at_least, of } => {
+            if *at_least > of.len() {
+                issue(path.clone(), format!("needs {} of only {} options, so can never be met", at_least, of.len()));
+            }
+            for (i, p) in of.iter().enumerate() {
+                check_prerequisite(game, p, format!("{}.of[{}]", path, i), issue);
+            }
+        },
+        Prerequisite::Not { not } => {
+            check_prerequisite(game, not, format!("{}.not", path), issue);
+        }
+    }
+}
+
+// Modifiers whose prerequisites could all be met, ignoring any "not" (which
+// depends on the player's choices). Unknown names are reported elsewhere, so
+// are assumed attainable here.
+fn attainable_modifiers(game: &Game) -> HashSet<&str> {
+    let mut attainable: HashSet<&str> = HashSet::new();
+    loop {
+        let owned = |n: &str| attainable.contains(n) || !game.modifiers.contains_key(n);
+        let newly: Vec<&str> = game.modifiers.iter()
+            .filter(|(name, m)| !attainable.contains(name.as_str())
+                    && m.locked_by.iter().all(|p| p.is_met(&owned, &|_: &str| false)))
+            .map(|(name, _)| name.as_str())
+            .collect();
+        if newly.is_empty() {
+            return attainable;
+        }
+        attainable.extend(newly);
+    }
+}
+
+// Follows only what holds each unattainable modifier up, so every cycle found
+// is one there is no way around.
+fn find_cycles<'a>(game: &'a Game, attainable: &HashSet<&str>, mod_name: &'a str,
+                   visits: &mut HashMap<&'a str, Visit>, stack: &mut Vec<&'a str>,
+                   issue: &mut impl FnMut(String, String)) {
+    if attainable.contains(mod_name) {
+        return;
+    }
+    match visits.get(mod_name) {
+        Some(Visit::Done) => return,
+        Some(Visit::InProgress) => {
+            let start = stack.iter().position(|n| *n == mod_name).unwrap_or(0);
+            let mut cycle: Vec<&str> = stack[start..].to_vec();
+            cycle.push(mod_name);
+            issue(format!("modifiers[{:?}].locked_by", mod_name),
+                  format!("prerequisite cycle: {}", cycle.join(" -> ")));
+            return;
+        },
+        None => ()
+    }
+
+    let modifier = match game.modifiers.get(mod_name) {
+        Some(modifier) => modifier,
+        None => return
+    };
+
+    visits.insert(mod_name, Visit::InProgress);
+    stack.push(mod_name);
+    let owned = |n: &str| attainable.contains(n) || !game.modifiers.contains_key(n);
+    for prerequisite in &modifier.locked_by {
+        for locking_mod_name in prerequisite.blocking_names(&owned) {
+            find_cycles(game, attainable, locking_mod_name, visits, stack, issue);
+        }
+    }
+    stack.pop();
+    visits.insert(mod_name, Visit::Done);
+}
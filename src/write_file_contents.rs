@@ -1,8 +1,15 @@
-use std::fs::write;
+use std::fs::{create_dir_all, write};
+use std::path::Path;
 
-pub fn write_contents(filename: &str, contents: &str) -> Result<(), String> {
-    match write(filename, contents) {
-        Ok(_) => Ok(()),
-        Err(_) => Err(format!("failed to write contents of file \"{filename}\""))
+use crate::error::GreyGooError;
+
+pub fn write_contents<P: AsRef<Path>>(filename: P, contents: &str) -> Result<(), GreyGooError> {
+    let filename = filename.as_ref();
+    if let Some(parent) = filename.parent() {
+        if !parent.as_os_str().is_empty() {
+            create_dir_all(parent).map_err(|e| GreyGooError::io(parent, e))?;
+        }
     }
-}
\ No newline at end of file
+
+    write(filename, contents).map_err(|e| GreyGooError::io(filename, e))
+}
@@ -0,0 +1,53 @@
+use std::fs::remove_file;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use greygoo::clock::{Clock, ManualClock};
+use greygoo::game::Game;
+use greygoo::state::State;
+
+const START_TS: i64 = 1_700_000_000;
+
+fn save_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("greygoo-{}-{}.json", name, std::process::id()))
+}
+
+// Loading the save, as every command does.
+fn load(path: &PathBuf, clock: &Rc<ManualClock>) -> State {
+    let clock: Rc<dyn Clock> = clock.clone();
+    State::load_with_clock(path, Game::builtin(), clock).unwrap()
+}
+
+#[test]
+fn only_a_check_in_resets_the_away_summary() {
+    let path = save_path("away");
+    let clock = Rc::new(ManualClock::new(START_TS));
+    let mut w = State::new_with_clock(Game::builtin(), clock.clone());
+    w.start_trial().unwrap();
+    w.mark_seen();
+    w.save(&path).unwrap();
+
+    // Two days away, then something other than a check-in, e.g. --log
+    clock.advance(2 * 86400);
+    let w = load(&path, &clock);
+    w.save(&path).unwrap();
+
+    // Checking in two minutes later still covers the whole time away
+    clock.advance(120);
+    let mut w = load(&path, &clock);
+    let summary = w.away_summary().unwrap();
+    assert_eq!(summary.away, 2 * 86400 + 120);
+    assert!(!summary.events.is_empty());
+    assert!(summary.mass_growth.is_some());
+    w.mark_seen();
+    w.save(&path).unwrap();
+
+    // and the next check-in only covers the time since
+    clock.advance(60);
+    let w = load(&path, &clock);
+    let summary = w.away_summary().unwrap();
+    assert_eq!(summary.away, 60);
+    assert!(summary.events.is_empty());
+
+    remove_file(&path).unwrap();
+}
@@ -0,0 +1,72 @@
+use std::rc::Rc;
+
+use greygoo::clock::ManualClock;
+use greygoo::event::EventOutcome;
+use greygoo::game::Game;
+use greygoo::log::LogEvent;
+use greygoo::state::State;
+use greygoo::unease::UneaseBand;
+
+const START_TS: i64 = 1_700_000_000;
+
+fn logged_refund(w: &State, mod_name: &str) -> f64 {
+    w.log.iter()
+        .find_map(|e| match &e.event {
+            LogEvent::ResearchCancelled { name, refund, .. } if name == mod_name => Some(*refund),
+            _ => None
+        })
+        .expect("cancellation logged")
+}
+
+#[test]
+fn cancelling_refunds_only_what_fits_under_the_cap() {
+    let clock = Rc::new(ManualClock::new(START_TS));
+    let mut w = State::new_with_clock(Game::builtin(), clock.clone());
+    w.game.inspiration_cap = Some(1f64);
+    w.game.modifiers.get_mut("miniaturisation I").unwrap().inspiration_cost = 0.4;
+    w.scientific_inspiration = 0.45;
+    w.try_research_modifier("miniaturisation I").unwrap();
+    assert_eq!(w.modifiers_in_progress[0].inspiration_spent, 0.4);
+
+    // Nearly at the cap by the time it is cancelled
+    w.scientific_inspiration = 0.95;
+    let cancelled = w.cancel_research("miniaturisation I").unwrap().unwrap();
+
+    assert_eq!(w.scientific_inspiration, 1f64);
+    assert!((cancelled.refund - 0.05).abs() < 1e-9, "refund {}", cancelled.refund);
+    assert_eq!(logged_refund(&w, "miniaturisation I"), cancelled.refund);
+}
+
+#[test]
+fn blocking_research_in_progress_cancels_it() {
+    let clock = Rc::new(ManualClock::new(START_TS));
+    let mut w = State::new_with_clock(Game::builtin(), clock.clone());
+    w.game.events.clear();
+    w.game.unease_bands = vec![UneaseBand {
+        name: "ethics committee".to_string(),
+        description: String::new(),
+        threshold: 40f64,
+        outcome: EventOutcome { block_modifier: Some("hive mind".to_string()), ..Default::default() }
+    }];
+    w.active_modifiers = vec!["miniaturisation I".to_string(), "miniaturisation II".to_string()];
+    w.scientific_inspiration = 4f64;
+    w.try_research_modifier("hive mind").unwrap();
+    w.queue_research_modifier("self-assembly").unwrap();
+    w.queue_research_modifier("public relations").unwrap();
+    assert_eq!(w.research_queue, ["self-assembly", "public relations"]);
+
+    // A quarter of the way through, the committee steps in
+    w.population_unease = 50f64;
+    clock.advance(1800);
+    w.update().unwrap();
+
+    assert!(w.locked_out.iter().any(|n| n == "hive mind"));
+    assert!(logged_refund(&w, "hive mind") > 0f64);
+    assert!(w.scientific_inspiration > 1f64, "inspiration {}", w.scientific_inspiration);
+
+    // Self-assembly can no longer be reached, and the freed slot goes to the
+    // next thing in the queue
+    assert!(w.research_queue.is_empty());
+    let in_progress: Vec<(&str, i64)> = w.modifiers_in_progress.iter().map(|r| (r.name.as_str(), r.start_ts)).collect();
+    assert_eq!(in_progress, [("public relations", START_TS + 1800)]);
+}
@@ -0,0 +1,78 @@
+use std::rc::Rc;
+
+use greygoo::clock::ManualClock;
+use greygoo::game::Game;
+use greygoo::log::LogEvent;
+use greygoo::state::State;
+
+const START_TS: i64 = 1_700_000_000;
+
+// Research into miniaturisation I, with II queued until there is enough
+// inspiration for it, and a trial running alongside.
+fn new_game(clock: &Rc<ManualClock>) -> State {
+    let mut w = State::new_with_clock(Game::builtin(), clock.clone());
+    w.scientific_inspiration = 0.45;
+    w.try_research_modifier("miniaturisation I").unwrap();
+    w.queue_research_modifier("miniaturisation II").unwrap();
+    w.start_trial().unwrap();
+    w
+}
+
+// Catches up over `total` seconds in steps of `step`.
+fn play(total: i64, step: i64) -> State {
+    let clock = Rc::new(ManualClock::new(START_TS));
+    let mut w = new_game(&clock);
+    let mut elapsed = 0;
+    while elapsed < total {
+        let dt = step.min(total - elapsed);
+        clock.advance(dt);
+        elapsed += dt;
+        w.update().unwrap();
+    }
+    w
+}
+
+fn assert_same(a: &State, b: &State) {
+    let close = |x: f64, y: f64| (x - y).abs() <= 1e-9 * x.abs().max(y.abs()).max(1f64);
+
+    assert_eq!(a.active_modifiers, b.active_modifiers);
+    assert_eq!(a.research_queue, b.research_queue);
+    let in_progress = |w: &State| w.modifiers_in_progress.iter().map(|r| (r.name.clone(), r.end_ts)).collect::<Vec<_>>();
+    assert_eq!(in_progress(a), in_progress(b));
+
+    let log = |w: &State| w.log.iter().map(|e| (e.ts, e.event.kind().name())).collect::<Vec<_>>();
+    assert_eq!(log(a), log(b));
+
+    assert!(close(a.population_unease, b.population_unease), "unease {} != {}", a.population_unease, b.population_unease);
+    assert!(close(a.scientific_inspiration, b.scientific_inspiration),
+            "inspiration {} != {}", a.scientific_inspiration, b.scientific_inspiration);
+
+    let trial_a = a.trial_in_progress.as_ref().expect("trial still running");
+    let trial_b = b.trial_in_progress.as_ref().expect("trial still running");
+    assert_eq!(trial_a.last_update_ts, trial_b.last_update_ts);
+    assert!(close(trial_a.bot_mass, trial_b.bot_mass), "bot mass {} != {}", trial_a.bot_mass, trial_b.bot_mass);
+}
+
+#[test]
+fn one_jump_matches_many_small_steps() {
+    let jumped = play(3600, 3600);
+    let stepped = play(3600, 8);
+    assert_same(&jumped, &stepped);
+
+    // Both pieces of research finished along the way, the second starting
+    // once the trial had earned enough inspiration for it
+    assert_eq!(jumped.active_modifiers, vec!["miniaturisation I", "miniaturisation II"]);
+    let started_ts = jumped.log.iter()
+        .find(|e| matches!(&e.event, LogEvent::ResearchStarted { name, .. } if name == "miniaturisation II"))
+        .map(|e| e.ts)
+        .unwrap();
+    assert!(started_ts > START_TS + 300, "started at {}", started_ts - START_TS);
+}
+
+#[test]
+fn check_in_frequency_does_not_matter_over_a_day() {
+    let jumped = play(86400, 86400);
+    for step in [1, 60, 3600, 7 * 3600] {
+        assert_same(&jumped, &play(86400, step));
+    }
+}
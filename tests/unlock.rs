@@ -0,0 +1,92 @@
+use std::fs::{remove_file, write};
+use std::rc::Rc;
+
+use greygoo::clock::ManualClock;
+use greygoo::game::Game;
+use greygoo::outcome::{RejectReason, ResearchOutcome};
+use greygoo::state::State;
+use greygoo::unlock::LockExplanation;
+
+const START_TS: i64 = 1_700_000_000;
+
+// Game data with only the given modifiers, each taking an hour.
+fn game_with(name: &str, locked_by: &[(&str, &str)]) -> Game {
+    let modifiers: Vec<String> = locked_by.iter()
+        .map(|(mod_name, locked_by)| format!(
+            r#""{}": {{"description": "", "effects": {{"default": {{}}}}, "time_cost": "1h", "locked_by": {}}}"#,
+            mod_name, locked_by))
+        .collect();
+    let source = format!(r#"{{"tau": 300, "world_mass": 1e27, "modifiers": {{{}}}}}"#, modifiers.join(", "));
+
+    let path = std::env::temp_dir().join(format!("greygoo-{}-{}.json", name, std::process::id()));
+    write(&path, source).unwrap();
+    let game = Game::load(&path).unwrap();
+    remove_file(&path).unwrap();
+    game
+}
+
+fn lock_explanation(w: &mut State, mod_name: &str) -> LockExplanation {
+    match w.try_research_modifier(mod_name).unwrap() {
+        ResearchOutcome::Rejected(rejected) => match rejected.reason {
+            RejectReason::Locked(explanation) => explanation,
+            _ => panic!("\"{}\" rejected for another reason", mod_name)
+        },
+        _ => panic!("\"{}\" was not rejected", mod_name)
+    }
+}
+
+#[test]
+fn any_of_cycles_are_planned_around() {
+    let game = game_with("any-of-cycle", &[
+        ("A", r#"[{"any_of": ["B", "C"]}]"#),
+        ("B", r#"[{"any_of": ["A", "D"]}]"#),
+        ("C", r#"["E"]"#),
+        ("D", r#"["E"]"#),
+        ("E", "[]")
+    ]);
+    let mut w = State::new_with_clock(game, Rc::new(ManualClock::new(START_TS)));
+
+    let a = lock_explanation(&mut w, "A");
+    assert!(a.reachable);
+    assert_eq!(a.path, ["E", "C"]);
+    assert_eq!(a.total_time, 7200);
+
+    let b = lock_explanation(&mut w, "B");
+    assert!(b.reachable);
+    assert_eq!(b.path, ["E", "D"]);
+}
+
+#[test]
+fn cycles_with_no_way_out_are_unreachable() {
+    let game = game_with("closed-cycle", &[
+        ("A", r#"[{"any_of": ["B"]}]"#),
+        ("B", r#"[{"at_least": 1, "of": ["A"]}]"#)
+    ]);
+    let mut w = State::new_with_clock(game, Rc::new(ManualClock::new(START_TS)));
+
+    let a = lock_explanation(&mut w, "A");
+    assert!(!a.reachable);
+    assert!(a.path.is_empty());
+}
+
+#[test]
+fn researching_one_branch_locks_out_its_rival() {
+    let mut w = State::new_with_clock(Game::builtin(), Rc::new(ManualClock::new(START_TS)));
+    w.active_modifiers = vec!["miniaturisation I".to_string(), "miniaturisation II".to_string()];
+    w.scientific_inspiration = 10f64;
+
+    // Queued research doesn't commit to a branch yet
+    assert!(matches!(w.try_research_modifier("second lab").unwrap(), ResearchOutcome::Started(_)));
+    assert!(matches!(w.queue_research_modifier("swarm intelligence").unwrap(), ResearchOutcome::Queued(_)));
+    assert!(w.locked_out.is_empty());
+
+    w.cancel_research("second lab").unwrap();
+    assert_eq!(w.modifiers_in_progress[0].name, "swarm intelligence");
+    assert_eq!(w.locked_out, ["hive mind"]);
+    assert!(w.research_queue.is_empty());
+
+    // Even if the research is then cancelled
+    w.cancel_research("swarm intelligence").unwrap();
+    assert_eq!(w.locked_out, ["hive mind"]);
+    assert!(!lock_explanation(&mut w, "hive mind").reachable);
+}